@@ -0,0 +1,87 @@
+#!/usr/bin/env python3
+"""Build the bundled gazetteer, gazetteer/gazetteer.tsv.z.
+
+With no arguments it compresses gazetteer/seed.tsv, a short list of major
+cities. For the full set of places with more than 15000 people, run
+
+    scripts/build_gazetteer.py --geonames
+
+which downloads cities15000.zip, countryInfo.txt and admin1CodesASCII.txt
+from https://download.geonames.org/export/dump/, or pass copies of them
+already downloaded, with the first unzipped:
+
+    scripts/build_gazetteer.py cities15000.txt countryInfo.txt admin1CodesASCII.txt
+
+Output rows are name, country, subdivision, latitude, longitude and
+population, tab separated and largest first, zlib compressed.
+"""
+
+import io
+import sys
+import tempfile
+import urllib.request
+import zipfile
+import zlib
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+SEED = ROOT / "gazetteer" / "seed.tsv"
+OUTPUT = ROOT / "gazetteer" / "gazetteer.tsv.z"
+GEONAMES = "https://download.geonames.org/export/dump/"
+
+
+def read_tsv(path):
+    with open(path, encoding="utf-8") as f:
+        for line in f:
+            if line.strip() and not line.startswith("#"):
+                yield line.rstrip("\n").split("\t")
+
+
+def from_geonames(cities, countries, admin1):
+    country_names = {row[0]: row[4] for row in read_tsv(countries)}
+    admin1_names = {row[0]: row[1] for row in read_tsv(admin1)}
+    for row in read_tsv(cities):
+        name, lat, lon, country, admin1_code, population = (
+            row[1], row[4], row[5], row[8], row[10], row[14],
+        )
+        yield [
+            name,
+            country_names.get(country, country),
+            admin1_names.get(f"{country}.{admin1_code}", ""),
+            f"{float(lat):.4f}",
+            f"{float(lon):.4f}",
+            population,
+        ]
+
+
+def download_geonames(directory):
+    """Download the GeoNames files into `directory`, returning their paths."""
+    def get(name):
+        with urllib.request.urlopen(GEONAMES + name, timeout=60) as response:
+            return response.read()
+
+    with zipfile.ZipFile(io.BytesIO(get("cities15000.zip"))) as archive:
+        archive.extract("cities15000.txt", directory)
+    paths = [directory / "cities15000.txt"]
+    for name in ["countryInfo.txt", "admin1CodesASCII.txt"]:
+        (directory / name).write_bytes(get(name))
+        paths.append(directory / name)
+    return paths
+
+
+def main(args):
+    if args == ["--geonames"]:
+        with tempfile.TemporaryDirectory() as directory:
+            rows = list(from_geonames(*download_geonames(Path(directory))))
+    elif args:
+        rows = list(from_geonames(*args))
+    else:
+        rows = list(read_tsv(SEED))
+    rows.sort(key=lambda row: -int(row[5]))
+    data = "".join("\t".join(row) + "\n" for row in rows).encode("utf-8")
+    OUTPUT.write_bytes(zlib.compress(data, 9))
+    print(f"{len(rows)} places, {len(data)} bytes, {OUTPUT.stat().st_size} compressed")
+
+
+if __name__ == "__main__":
+    main(sys.argv[1:])
@@ -0,0 +1,56 @@
+#!/usr/bin/env python3
+"""Record backend responses into fixtures/ for the mock data mode.
+
+The mock mode (`?mock` in the URL, or the `mock` cargo feature) serves
+these files instead of calling the backend:
+
+    scripts/record_fixtures.py http://localhost:8000
+
+Objects are recorded from Greenwich at the March 2024 equinox evening;
+the mock moves them to the position and time being shown.
+"""
+
+import json
+import sys
+import urllib.parse
+import urllib.request
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+FIXTURES = ROOT / "fixtures"
+OBJECTS = ["Sun", "Mercury", "Venus", "Mars", "Moon", "Jupiter", "Saturn"]
+POSITION = {"lat": 51.4769, "lon": -0.0005, "elevation": 0.0}
+WHEN = "2024-03-20T21:00:00"
+SEARCHES = ["Greenwich", "London", "Edinburgh", "New York", "Flagstaff", "Hilo",
+            "La Serena", "Cape Town", "Sydney", "Christchurch", "Tokyo", "Reykjavik"]
+
+
+def get(base_url, path, params):
+    url = f"{base_url}{path}?{urllib.parse.urlencode(params)}"
+    with urllib.request.urlopen(url) as response:
+        return json.load(response)
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(f"usage: {sys.argv[0]} BACKEND_URL")
+    base_url = sys.argv[1].rstrip("/")
+    FIXTURES.mkdir(exist_ok=True)
+
+    objects = [
+        get(base_url, "/get_astron_object_data", {"name": name, **POSITION, "when": WHEN})
+        for name in OBJECTS
+    ]
+    (FIXTURES / "astron_objects.json").write_text(json.dumps(objects, indent=2) + "\n")
+
+    # the best match for each search, so the mock can filter them by name
+    items = []
+    for query in SEARCHES:
+        found = get(base_url, "/search", {"q": query, "max_results": 1, "fuzzy": 0.0})["items"]
+        items.extend(item for item in found if item not in items)
+    (FIXTURES / "search.json").write_text(json.dumps({"items": items}, indent=2) + "\n")
+    print(f"recorded {len(objects)} objects and {len(items)} places")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,44 @@
+#!/usr/bin/env python3
+"""Refresh the bundled satellite elements, satellites/satellites.tle.
+
+The app fetches current elements from Celestrak when it starts and only
+falls back to the bundled ones offline. Run this by hand, or with
+`make satellites`, before a release and commit the result:
+
+    scripts/update_satellites.py [url]
+
+SGP4 predictions drift by kilometres a day away from the epoch of the
+elements. The app warns about elements more than 30 days old and leaves
+out satellites whose elements are more than 90 days old.
+"""
+
+import sys
+import urllib.request
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+OUTPUT = ROOT / "satellites" / "satellites.tle"
+URL = "https://celestrak.org/NORAD/elements/gp.php?GROUP=visual&FORMAT=tle"
+
+
+def fetch(url):
+    with urllib.request.urlopen(url, timeout=30) as response:
+        text = response.read().decode("ascii")
+    lines = [line.rstrip() for line in text.splitlines() if line.strip()]
+    if not any(line.startswith("1 ") for line in lines):
+        raise ValueError(f"no element sets in {url}")
+    return lines
+
+
+def main(args):
+    url = args[0] if args else URL
+    try:
+        lines = fetch(url)
+    except (OSError, ValueError) as e:
+        sys.exit(f"couldn't fetch {url}: {e}")
+    OUTPUT.write_text("\n".join(lines) + "\n", encoding="ascii")
+    print(f"wrote {sum(line.startswith('1 ') for line in lines)} satellites to {OUTPUT}")
+
+
+if __name__ == "__main__":
+    main(sys.argv[1:])
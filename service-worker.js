@@ -0,0 +1,182 @@
+// Makes the app work offline and shows its alert notifications.
+//
+// - Pages are network first, so a new build is picked up as soon as there
+//   is a connection, falling back to the last page seen.
+// - The wasm bundle, scripts, styles, fonts and icons are cache first.
+//   Trunk hashes their names, so a new build never hits stale entries, and
+//   entries no longer referenced by the page are dropped.
+// - The gazetteer is only cached once the app first asks for it, since it
+//   is large and not every visit searches.
+// - /search and /get_astron_object_data are network first, falling back
+//   to the last answer for the same query.
+//
+// Alerts are scheduled by the page, see src/alerts.rs. Where the browser
+// has Notification Triggers it shows them at their time even with no page
+// open; otherwise the page's timers fire them. Either way this worker
+// brings the app back when one is clicked.
+
+const STATIC_CACHE = "planet-tracker-static-v1";
+const API_CACHE = "planet-tracker-api-v1";
+const CACHES = [STATIC_CACHE, API_CACHE];
+
+const APP_SHELL = [
+    "/",
+    "/manifest.webmanifest",
+    "/icons/icon-192.png",
+    "/icons/icon-512.png",
+    "/satellites.tle",
+];
+const LAZY_ASSETS = ["/gazetteer.tsv.z"];
+const API_PATHS = ["/search", "/get_astron_object_data"];
+const STATIC_DESTINATIONS = ["script", "style", "font", "image", "manifest"];
+
+// asset urls referenced by a page, eg the hashed wasm, js and css
+function assetUrls(html) {
+    const urls = new Set();
+    for (const [, url] of html.matchAll(/(?:href|src)=["']([^"']+)["']/g)) {
+        if (!url.startsWith("http") && !url.startsWith("data:")) {
+            urls.add(new URL(url, self.location.origin).href);
+        }
+    }
+    return urls;
+}
+
+// cache `page` and everything it references, and drop assets it no longer uses
+async function cacheShell(page) {
+    const cache = await caches.open(STATIC_CACHE);
+    const html = await page.clone().text();
+    await cache.put("/", page);
+    const wanted = assetUrls(html);
+    for (const url of APP_SHELL) {
+        wanted.add(new URL(url, self.location.origin).href);
+    }
+    // wasm is loaded by the generated script rather than linked from the page
+    for (const url of [...wanted]) {
+        if (url.endsWith(".js")) {
+            wanted.add(url.replace(/\.js$/, "_bg.wasm"));
+        }
+    }
+    await Promise.all(
+        [...wanted].map(async (url) => {
+            if (!(await cache.match(url))) {
+                const response = await fetch(url).catch(() => null);
+                if (response && response.ok) {
+                    await cache.put(url, response);
+                }
+            }
+        })
+    );
+    for (const request of await cache.keys()) {
+        const path = new URL(request.url).pathname;
+        if (path !== "/" && !wanted.has(request.url) && !LAZY_ASSETS.includes(path)) {
+            await cache.delete(request);
+        }
+    }
+}
+
+// astronomical data is asked for at the current time, so offline the last
+// answer for the same object and place is the best there is
+function apiCacheKey(request) {
+    const url = new URL(request.url);
+    url.searchParams.delete("when");
+    return url.href;
+}
+
+async function networkFirst(request, cacheName, key) {
+    const cache = await caches.open(cacheName);
+    try {
+        const response = await fetch(request);
+        if (response.ok) {
+            await cache.put(key, response.clone());
+        }
+        return response;
+    } catch (error) {
+        const cached = await cache.match(key);
+        if (cached) {
+            return cached;
+        }
+        throw error;
+    }
+}
+
+async function navigate(request) {
+    try {
+        const response = await fetch(request);
+        if (response.ok) {
+            await cacheShell(response.clone());
+        }
+        return response;
+    } catch (error) {
+        const cached = await caches.match("/");
+        if (cached) {
+            return cached;
+        }
+        throw error;
+    }
+}
+
+async function cacheFirst(request) {
+    const cached = await caches.match(request);
+    if (cached) {
+        return cached;
+    }
+    const response = await fetch(request);
+    if (response.ok) {
+        const cache = await caches.open(STATIC_CACHE);
+        await cache.put(request, response.clone());
+    }
+    return response;
+}
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(
+        fetch("/")
+            .then((page) => cacheShell(page))
+            .then(() => self.skipWaiting())
+    );
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches
+            .keys()
+            .then((names) => Promise.all(names.filter((name) => !CACHES.includes(name)).map((name) => caches.delete(name))))
+            .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener("fetch", (event) => {
+    const request = event.request;
+    if (request.method !== "GET") {
+        return;
+    }
+    const url = new URL(request.url);
+    if (request.mode === "navigate") {
+        event.respondWith(navigate(request));
+    } else if (url.origin === self.location.origin && API_PATHS.includes(url.pathname)) {
+        event.respondWith(networkFirst(request, API_CACHE, apiCacheKey(request)));
+    } else if (
+        STATIC_DESTINATIONS.includes(request.destination) ||
+        url.pathname.endsWith(".wasm") ||
+        LAZY_ASSETS.includes(url.pathname) ||
+        url.pathname === "/satellites.tle"
+    ) {
+        event.respondWith(cacheFirst(request));
+    }
+});
+
+self.addEventListener("message", (event) => {
+    if (event.data === "skipWaiting") {
+        self.skipWaiting();
+    }
+});
+
+self.addEventListener("notificationclick", (event) => {
+    event.notification.close();
+    event.waitUntil(
+        self.clients.matchAll({ type: "window", includeUncontrolled: true }).then((windows) => {
+            const open = windows.find((client) => "focus" in client);
+            return open ? open.focus() : self.clients.openWindow("/");
+        })
+    );
+});
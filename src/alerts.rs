@@ -0,0 +1,418 @@
+use std::fmt;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use enum_iterator::{all, Sequence};
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use leptos::{leptos_dom::helpers::TimeoutHandle, *};
+use leptos_use::storage::{use_local_storage, JsonCodec};
+use serde::{Deserialize, Deserializer, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Notification, NotificationOptions, NotificationPermission, ServiceWorkerRegistration};
+
+use crate::{
+    models::{AstronObject, SkyTarget, TargetId, TargetKind},
+    satellites::{use_satellites, SatellitePass},
+    service_worker::use_service_worker,
+    utils::{naive_utc_to_local, rad2deg},
+    AstronObjectsRw,
+};
+
+const ALERTS_STORAGE_KEY: &str = "planet-tracker-alerts";
+/// starts the tag of every alert notification, to tell them from others
+const ALERT_TAG_PREFIX: &str = "alert-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+pub enum AlertEvent {
+    Rise,
+    Set,
+    /// a visible pass of a satellite
+    SatellitePass,
+}
+
+impl AlertEvent {
+    /// satellites pass over, everything else rises and sets
+    pub fn applies_to(&self, target: &TargetId) -> bool {
+        (*self == Self::SatellitePass) == target.is_satellite()
+    }
+}
+
+impl fmt::Display for AlertEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rise => write!(f, "rises"),
+            Self::Set => write!(f, "sets"),
+            Self::SatellitePass => write!(f, "passes over"),
+        }
+    }
+}
+
+/// rules saved before targets had ids named the planet, eg `"Saturn"`
+fn target_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TargetId, D::Error> {
+    String::deserialize(deserializer).map(|id| TargetId(id.to_lowercase()))
+}
+
+/// "Notify me `minutes_before` minutes before `object` `event`s"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: u32,
+    #[serde(deserialize_with = "target_id")]
+    pub object: TargetId,
+    pub event: AlertEvent,
+    pub minutes_before: i64,
+    pub enabled: bool,
+}
+
+impl AlertRule {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            object: TargetId::from(&AstronObject::Saturn),
+            event: AlertEvent::Rise,
+            minutes_before: 15,
+            enabled: true,
+        }
+    }
+
+    /// Alert on `object` instead, switching to an event it has if need be.
+    pub fn set_object(&mut self, object: TargetId) {
+        if !self.event.applies_to(&object) {
+            self.event = all::<AlertEvent>()
+                .find(|event| event.applies_to(&object))
+                .unwrap_or(AlertEvent::Rise);
+        }
+        self.object = object;
+    }
+
+    /// (name, time, what happens) of each event the rule is about
+    fn events(
+        &self,
+        objs: &[SkyTarget],
+        passes: &[SatellitePass],
+    ) -> Vec<(String, NaiveDateTime, String)> {
+        let at = |t: &NaiveDateTime| naive_utc_to_local(t).format("%H:%M").to_string();
+        match self.event {
+            AlertEvent::Rise | AlertEvent::Set => objs
+                .iter()
+                .find(|obj| obj.id == self.object)
+                .and_then(|obj| {
+                    let time = match self.event {
+                        AlertEvent::Rise => obj.rising_time,
+                        _ => obj.setting_time,
+                    }?;
+                    let what = format!("{} {} at {}", obj.name, self.event, at(&time));
+                    Some((obj.name.clone(), time, what))
+                })
+                .into_iter()
+                .collect(),
+            AlertEvent::SatellitePass => passes
+                .iter()
+                .filter(|pass| {
+                    pass.visible
+                        && TargetId::new(TargetKind::Satellite, &pass.satellite) == self.object
+                })
+                .map(|pass| {
+                    let detail = format!(
+                        "{} passes over at {}, up to {:.0}°",
+                        pass.satellite,
+                        at(&pass.start.when),
+                        rad2deg(pass.max.el)
+                    );
+                    (pass.satellite.clone(), pass.start.when, detail)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A notification due at `at` (UTC). `tag` is the same each time the same
+/// event is scheduled, so a notification is never shown twice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledAlert {
+    pub tag: String,
+    pub title: String,
+    pub body: String,
+    pub at: NaiveDateTime,
+}
+
+/// Notifications still to come for the enabled `rules`, from the rise and
+/// set times in `objs` and the satellite `passes`.
+pub fn schedule(
+    rules: &[AlertRule],
+    objs: &[SkyTarget],
+    passes: &[SatellitePass],
+    now: NaiveDateTime,
+) -> Vec<ScheduledAlert> {
+    rules
+        .iter()
+        .filter(|rule| rule.enabled)
+        .flat_map(|rule| {
+            rule.events(objs, passes)
+                .into_iter()
+                .filter_map(move |(name, event_time, body)| {
+                    let at = event_time - Duration::minutes(rule.minutes_before);
+                    (at > now).then(|| ScheduledAlert {
+                        tag: format!("{}{}-{}", ALERT_TAG_PREFIX, rule.id, event_time.and_utc().timestamp()),
+                        title: format!("{} {} in {} minutes", name, rule.event, rule.minutes_before),
+                        body,
+                        at,
+                    })
+                })
+        })
+        .collect()
+}
+
+/// `TimestampTrigger` from Notification Triggers, for a notification the
+/// browser shows at `at` with no page open. Only some Chromium builds have it.
+fn timestamp_trigger(at: NaiveDateTime) -> Option<JsValue> {
+    let constructor = Reflect::get(&js_sys::global(), &JsValue::from_str("TimestampTrigger"))
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?;
+    let args = Array::of1(&JsValue::from_f64(at.and_utc().timestamp_millis() as f64));
+    Reflect::construct(&constructor, &args).ok()
+}
+
+/// The permission to show notifications, taken as denied where there's no
+/// Notification API at all, as in iOS Safari outside an installed app
+pub fn notification_permission() -> NotificationPermission {
+    if Reflect::has(&js_sys::global(), &JsValue::from_str("Notification")).unwrap_or(false) {
+        Notification::permission()
+    } else {
+        NotificationPermission::Denied
+    }
+}
+
+/// whether alerts can fire with the tab in the background or closed
+pub fn background_alerts_supported() -> bool {
+    Reflect::has(&js_sys::global(), &JsValue::from_str("TimestampTrigger")).unwrap_or(false)
+}
+
+/// Close notifications scheduled with a trigger for earlier rules that
+/// aren't in `keep`.
+async fn cancel_triggered(registration: &ServiceWorkerRegistration, keep: &[String]) {
+    // set by hand, like `showTrigger`, as web-sys doesn't know the option
+    let filter = Object::new();
+    let _ = Reflect::set(&filter, &JsValue::from_str("includeTriggered"), &JsValue::TRUE);
+    let promise = Reflect::get(registration, &JsValue::from_str("getNotifications"))
+        .ok()
+        .and_then(|get| get.dyn_into::<Function>().ok())
+        .and_then(|get| get.call1(registration, &filter).ok())
+        .and_then(|promise| promise.dyn_into::<Promise>().ok());
+    let Some(promise) = promise else {
+        return;
+    };
+    let Ok(notifications) = JsFuture::from(promise).await else {
+        return;
+    };
+    for notification in Array::from(&notifications).iter() {
+        let notification = notification.unchecked_into::<Notification>();
+        let tag = notification.tag().unwrap_or_default();
+        if tag.starts_with(ALERT_TAG_PREFIX) && !keep.contains(&tag) {
+            notification.close();
+        }
+    }
+}
+
+/// Show `alert` now, or at its time if `trigger` is given.
+fn notify(
+    registration: Option<&ServiceWorkerRegistration>,
+    alert: &ScheduledAlert,
+    trigger: Option<JsValue>,
+) {
+    let mut options = NotificationOptions::new();
+    options.body(&alert.body).tag(&alert.tag);
+    if let Some(trigger) = trigger {
+        let _ = Reflect::set(&options, &JsValue::from_str("showTrigger"), &trigger);
+    }
+    // through the service worker if there is one, so that clicking the
+    // notification brings the app back
+    let _ = match registration {
+        Some(registration) => registration
+            .show_notification_with_options(&alert.title, &options)
+            .map(|_| ()),
+        None => Notification::new_with_options(&alert.title, &options).map(|_| ()),
+    };
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Alerts {
+    pub rules: Signal<Vec<AlertRule>>,
+    pub set_rules: WriteSignal<Vec<AlertRule>>,
+    pub permission: RwSignal<NotificationPermission>,
+}
+
+impl Alerts {
+    /// ask to show notifications, if not asked before
+    pub fn request_permission(&self) {
+        let permission = self.permission;
+        if notification_permission() == NotificationPermission::Denied {
+            return;
+        }
+        let Ok(promise) = Notification::request_permission() else {
+            return;
+        };
+        spawn_local(async move {
+            let _ = JsFuture::from(promise).await;
+            permission.set(notification_permission());
+        });
+    }
+}
+
+/// Keep alert rules in local storage and fire notifications for them from
+/// the rise and set times in `objs` and the satellite passes. Where the
+/// browser has Notification Triggers the service worker shows them at their
+/// time, tab open or not. Otherwise timers in the page fire them, which
+/// browsers delay or stop in background tabs.
+pub fn use_alerts(objs: AstronObjectsRw) -> Alerts {
+    let (rules, set_rules, _) = use_local_storage::<Vec<AlertRule>, JsonCodec>(ALERTS_STORAGE_KEY);
+    let permission = create_rw_signal(notification_permission());
+
+    let registration = use_service_worker().registration;
+    let passes = use_satellites().passes;
+
+    let timers = store_value(Vec::<TimeoutHandle>::new());
+    create_effect(move |_| {
+        timers.update_value(|timers| timers.drain(..).for_each(|timer| timer.clear()));
+        if permission.get() != NotificationPermission::Granted {
+            return;
+        }
+        let now = Utc::now().naive_utc();
+        let alerts = objs.with(|objs| {
+            passes.with(|passes| rules.with(|rules| schedule(rules, objs, passes, now)))
+        });
+
+        if let (Some(registration), true) = (registration.get(), background_alerts_supported()) {
+            spawn_local(async move {
+                let tags = alerts.iter().map(|alert| alert.tag.clone()).collect::<Vec<_>>();
+                cancel_triggered(&registration, &tags).await;
+                for alert in alerts {
+                    notify(Some(&registration), &alert, timestamp_trigger(alert.at));
+                }
+            });
+            return;
+        }
+        for alert in alerts {
+            let delay = (alert.at - now).to_std().unwrap_or_default();
+            let handle = set_timeout_with_handle(
+                move || notify(registration.get_untracked().as_ref(), &alert, None),
+                delay,
+            );
+            if let Ok(handle) = handle {
+                timers.update_value(|timers| timers.push(handle));
+            }
+        }
+    });
+
+    Alerts {
+        rules,
+        set_rules,
+        permission,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{models::AstronObjectResponse, satellites::PassPoint};
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 20)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn saturn() -> SkyTarget {
+        SkyTarget::from(AstronObjectResponse {
+            name: AstronObject::Saturn,
+            magnitude: 0.9,
+            size: 17.0,
+            az: 1.0,
+            el: -0.2,
+            ra: 5.9,
+            dec: -0.2,
+            setting_time: at(16, 0),
+            rising_time: at(22, 0),
+            when: at(20, 0),
+        })
+    }
+
+    fn iss_pass(start: NaiveDateTime, visible: bool) -> SatellitePass {
+        let point = |when| PassPoint {
+            when,
+            az: 0.0,
+            el: 0.5,
+            sunlit: visible,
+            magnitude: None,
+        };
+        SatellitePass {
+            satellite: "ISS (ZARYA)".to_string(),
+            start: point(start),
+            max: point(start + Duration::minutes(3)),
+            end: point(start + Duration::minutes(6)),
+            track: Vec::new(),
+            visible,
+            magnitude: None,
+        }
+    }
+
+    #[test]
+    fn rise_alerts_come_before_the_rise() {
+        let rule = AlertRule::new(1);
+        let alerts = schedule(&[rule], &[saturn()], &[], at(20, 0));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].at, at(21, 45));
+        assert_eq!(alerts[0].title, "Saturn rises in 15 minutes");
+        assert!(alerts[0].tag.starts_with(ALERT_TAG_PREFIX));
+
+        // already past, or switched off
+        assert!(schedule(&[AlertRule::new(1)], &[saturn()], &[], at(21, 50)).is_empty());
+        let off = AlertRule { enabled: false, ..AlertRule::new(1) };
+        assert!(schedule(&[off], &[saturn()], &[], at(20, 0)).is_empty());
+    }
+
+    #[test]
+    fn satellite_alerts_come_before_each_visible_pass() {
+        let mut rule = AlertRule::new(2);
+        rule.set_object(TargetId::new(TargetKind::Satellite, "ISS (ZARYA)"));
+        assert_eq!(rule.event, AlertEvent::SatellitePass);
+        rule.minutes_before = 5;
+
+        let passes = [
+            iss_pass(at(20, 30), true),
+            iss_pass(at(22, 5), false),
+            iss_pass(at(23, 40), true),
+        ];
+        let alerts = schedule(&[rule], &[saturn()], &passes, at(20, 0));
+        let times = alerts.iter().map(|alert| alert.at).collect::<Vec<_>>();
+        assert_eq!(times, [at(20, 25), at(23, 35)]);
+        assert_eq!(alerts[0].title, "ISS (ZARYA) passes over in 5 minutes");
+    }
+
+    #[test]
+    fn events_follow_the_target() {
+        let satellite = TargetId::new(TargetKind::Satellite, "ISS (ZARYA)");
+        let saturn = TargetId::from(&AstronObject::Saturn);
+        assert!(AlertEvent::SatellitePass.applies_to(&satellite));
+        assert!(!AlertEvent::Rise.applies_to(&satellite));
+        assert!(AlertEvent::Set.applies_to(&saturn));
+        assert!(!AlertEvent::SatellitePass.applies_to(&saturn));
+
+        let mut rule = AlertRule::new(3);
+        rule.set_object(satellite);
+        rule.set_object(TargetId::new(TargetKind::Custom, "Vega"));
+        assert_eq!(rule.event, AlertEvent::Rise);
+    }
+
+    #[test]
+    fn rules_saved_with_planet_names_still_load() {
+        let saved = r#"{"id":4,"object":"Saturn","event":"Set","minutes_before":10,"enabled":true}"#;
+        let rule: AlertRule = serde_json::from_str(saved).unwrap();
+        assert_eq!(rule.object, TargetId::from(&AstronObject::Saturn));
+        let again: AlertRule = serde_json::from_str(&serde_json::to_string(&rule).unwrap()).unwrap();
+        assert_eq!(again, rule);
+    }
+}
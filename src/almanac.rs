@@ -0,0 +1,150 @@
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    coords::{
+        angular_separation, equatorial_to_ecliptic, equatorial_to_horizontal, julian_date,
+        local_sidereal_time, mean_obliquity, sun_equatorial,
+    },
+    models::{Position, SkyTarget},
+};
+
+/// Sun altitudes in degrees at sunrise and sunset, and at the ends of
+/// civil, nautical and astronomical twilight
+pub const SUNSET_ALTITUDE: f64 = -0.833;
+pub const CIVIL_TWILIGHT: f64 = -6.0;
+pub const NAUTICAL_TWILIGHT: f64 = -12.0;
+pub const ASTRONOMICAL_TWILIGHT: f64 = -18.0;
+
+const SCAN_STEP_MINUTES: i64 = 10;
+
+/// altitude of the Sun at `t` (UTC) seen from `position`, in degrees
+pub fn sun_altitude(t: &NaiveDateTime, position: &Position) -> f64 {
+    let jd = julian_date(t);
+    let (ra, dec) = sun_equatorial(jd);
+    let lst = local_sidereal_time(jd, position.lon.to_radians());
+    let (_, el) = equatorial_to_horizontal(ra, dec, position.lat.to_radians(), lst);
+    el.to_degrees()
+}
+
+/// First time in [`start`, `end`] that `altitude_at` passes `altitude`,
+/// going down if `setting` and up otherwise, to within a minute.
+pub fn crossing(
+    altitude_at: impl Fn(&NaiveDateTime) -> f64,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    altitude: f64,
+    setting: bool,
+) -> Option<NaiveDateTime> {
+    let above = |t: &NaiveDateTime| altitude_at(t) > altitude;
+    let step = Duration::minutes(SCAN_STEP_MINUTES);
+    let mut t = start;
+    while t < end {
+        let next = t + step;
+        if above(&t) == setting && above(&next) != setting {
+            let (mut lo, mut hi) = (t, next);
+            while hi - lo > Duration::minutes(1) {
+                let mid = lo + (hi - lo) / 2;
+                if above(&mid) == setting {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            return Some(hi);
+        }
+        t = next;
+    }
+    None
+}
+
+/// Sunset, the ends of evening twilight, the starts of morning twilight and
+/// sunrise for one night, in UTC. Any that don't happen, as in summer at
+/// high latitudes, are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Twilight {
+    pub sunset: Option<NaiveDateTime>,
+    pub civil_dusk: Option<NaiveDateTime>,
+    pub nautical_dusk: Option<NaiveDateTime>,
+    pub astronomical_dusk: Option<NaiveDateTime>,
+    pub astronomical_dawn: Option<NaiveDateTime>,
+    pub nautical_dawn: Option<NaiveDateTime>,
+    pub civil_dawn: Option<NaiveDateTime>,
+    pub sunrise: Option<NaiveDateTime>,
+}
+
+impl Twilight {
+    /// Twilight for the night around `when`: the coming night during the
+    /// day, or the current one if the Sun is already down.
+    pub fn for_night(position: &Position, when: NaiveDateTime) -> Self {
+        // go back to the last time the Sun was up, so that a night already
+        // under way is found whole
+        let mut start = when;
+        let earliest = when - Duration::hours(24);
+        while start > earliest && sun_altitude(&start, position) <= SUNSET_ALTITUDE {
+            start -= Duration::minutes(SCAN_STEP_MINUTES);
+        }
+        let end = start + Duration::hours(36);
+
+        let sun = |t: &NaiveDateTime| sun_altitude(t, position);
+        let dusk = |altitude| crossing(sun, start, end, altitude, true);
+        let sunset = dusk(SUNSET_ALTITUDE);
+        let civil_dusk = dusk(CIVIL_TWILIGHT);
+        let nautical_dusk = dusk(NAUTICAL_TWILIGHT);
+        let astronomical_dusk = dusk(ASTRONOMICAL_TWILIGHT);
+
+        let midnight = astronomical_dusk
+            .or(nautical_dusk)
+            .or(civil_dusk)
+            .or(sunset)
+            .unwrap_or(start);
+        let dawn = |altitude| crossing(sun, midnight, end, altitude, false);
+        Self {
+            sunset,
+            civil_dusk,
+            nautical_dusk,
+            astronomical_dusk,
+            astronomical_dawn: dawn(ASTRONOMICAL_TWILIGHT),
+            nautical_dawn: dawn(NAUTICAL_TWILIGHT),
+            civil_dawn: dawn(CIVIL_TWILIGHT),
+            sunrise: dawn(SUNSET_ALTITUDE),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoonPhase {
+    /// Moon's ecliptic longitude less the Sun's, in degrees: 0 at new
+    /// Moon, 180 at full
+    pub elongation: f64,
+    /// fraction of the disc lit, from 0 to 1
+    pub illuminated: f64,
+}
+
+impl MoonPhase {
+    /// phase from the Sun's and Moon's positions, treating the Sun as
+    /// infinitely far away
+    pub fn new(sun: &SkyTarget, moon: &SkyTarget) -> Self {
+        let obliquity = mean_obliquity(julian_date(&moon.when));
+        let (sun_lon, _) = equatorial_to_ecliptic(sun.ra, sun.dec, obliquity);
+        let (moon_lon, _) = equatorial_to_ecliptic(moon.ra, moon.dec, obliquity);
+        let separation = angular_separation(sun.ra, sun.dec, moon.ra, moon.dec);
+        Self {
+            elongation: (moon_lon - sun_lon).to_degrees().rem_euclid(360.0),
+            illuminated: (1.0 - separation.cos()) / 2.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.elongation {
+            e if e < 22.5 => "New Moon",
+            e if e < 67.5 => "Waxing crescent",
+            e if e < 112.5 => "First quarter",
+            e if e < 157.5 => "Waxing gibbous",
+            e if e < 202.5 => "Full Moon",
+            e if e < 247.5 => "Waning gibbous",
+            e if e < 292.5 => "Last quarter",
+            e if e < 337.5 => "Waning crescent",
+            _ => "New Moon",
+        }
+    }
+}
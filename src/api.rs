@@ -4,7 +4,7 @@ use crate::{
     models::{
         AstronObjectQueryParams, AstronObjectResponse,
         SearchQueryParams, SearchResponse
-    }, errors::AppError
+    }, errors::AppError, mock
 };
 
 
@@ -15,6 +15,9 @@ fn base_url() -> String {
 
 pub async fn search(query: SearchQueryParams) -> Result<SearchResponse, AppError>
 {
+    if mock::enabled() {
+        return mock::search(query);
+    }
     let url = format!("{}/search", base_url());
     let client = reqwest::Client::new();
     let res = client.get(url)
@@ -31,6 +34,9 @@ pub async fn search(query: SearchQueryParams) -> Result<SearchResponse, AppError
 
 pub async fn get_astron_object_data(query: AstronObjectQueryParams) -> Result<AstronObjectResponse, AppError>
 {
+    if mock::enabled() {
+        return mock::get_astron_object_data(query);
+    }
     let url = format!("{}/get_astron_object_data", base_url());
     let client = reqwest::Client::new();
     let res = client.get(url)
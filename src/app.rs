@@ -1,59 +1,40 @@
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
 use enum_iterator::all;
 use futures::future::join_all;
 use leptos::{html::Div, *};
 use leptos_meta::*;
-use leptos_use::{use_element_size, use_resize_observer, UseElementSizeReturn};
+use leptos_use::use_resize_observer;
 use logging::log;
-use std::collections::HashMap;
 
 use crate::{
+    alerts::use_alerts,
     api::get_astron_object_data,
-    components::{GeoDateTimeSearch, PolarPlot, TextDisplay},
+    clock::provide_clock,
+    components::{
+        AlertSettings, BestTonight, CompassGuide, CompassToggle, CustomTargetList,
+        GeoDateTimeSearch, MinorBodyList, ObservingSheet, PanoramaPlot, PolarPlot,
+        SatellitePasses, TextDisplay, ThemeSelect,
+    },
+    custom_targets::use_custom_targets,
     errors::AppError,
+    gazetteer::provide_gazetteer,
+    minor_bodies::use_minor_bodies,
     models::{
-        AstronObject, AstronObjectQueryParams, AstronObjectResponse, Position,
-        SelectedAstronObjectResponse,
+        AstronObject, AstronObjectQueryParams, Position, SkyTarget, TargetId,
     },
+    orientation::use_device_orientation,
+    projection::ProjectionKind,
+    satellites::provide_satellites,
+    service_worker::provide_service_worker,
+    state::{provide_app_state, use_app_state},
+    theme::provide_theme,
 };
 
 pub const MIN_POLAR_PLOT_WIDTH: usize = 300;
 
-// async fn get_all_astron_object_data_dummy(position_time: (Position, DateTime<Utc>)) -> Result<Vec<AstronObjectResponse>, AppError>
-// {
-//     let (_, when) = position_time;
-//     let res = Ok(vec![
-//         AstronObjectResponse {
-//             name: AstronObject::Jupiter,
-//             magnitude: 0.0,
-//             size: 10.0,
-//             az: 5.06,
-//             el: 0.34,
-//             ra: 0.0,
-//             dec: 0.0,
-//             setting_time: when.naive_utc() + Duration::hours(2),
-//             rising_time: when.naive_utc() + Duration::hours(5),
-//             when: when.naive_utc()
-//         },
-//         AstronObjectResponse {
-//             name: AstronObject::Mars,
-//             magnitude: 0.0,
-//             size: 4.0,
-//             az: 1.74,
-//             el: 1.13,
-//             ra: 0.0,
-//             dec: 0.0,
-//             setting_time: when.naive_utc() + Duration::hours(2),
-//             rising_time: when.naive_utc() + Duration::hours(5),
-//             when: when.naive_utc()
-//         },
-//     ]);
-//     res
-// }
-
 async fn get_all_astron_object_data(
     position_time: (Position, DateTime<Utc>),
-) -> Result<Vec<AstronObjectResponse>, AppError> {
+) -> Result<Vec<SkyTarget>, AppError> {
     let (position, when) = position_time;
     let futs = all::<AstronObject>()
         .map(|obj| {
@@ -68,18 +49,64 @@ async fn get_all_astron_object_data(
         })
         .collect::<Vec<_>>();
 
-    let res = join_all(futs).await.into_iter().collect();
+    let res = join_all(futs)
+        .await
+        .into_iter()
+        .map(|res| res.map(SkyTarget::from))
+        .collect();
     res
 }
 
-pub type AstronObjectsRw = RwSignal<Vec<AstronObjectResponse>>;
-pub type SelectedRw = RwSignal<Option<AstronObject>>;
+/// every target shown: the server's planets and whatever else was added
+pub type AstronObjectsRw = RwSignal<Vec<SkyTarget>>;
+pub type SelectedRw = RwSignal<Option<TargetId>>;
+
+/// `obj` as it is now in `objs`, for rows and markers keyed by id that
+/// outlive refetches. Keeps the last version once it's gone.
+pub fn latest_target(objs: AstronObjectsRw, obj: SkyTarget) -> Memo<SkyTarget> {
+    create_memo(move |prev: Option<&SkyTarget>| {
+        objs.with(|objs| objs.iter().find(|o| o.id == obj.id).cloned())
+            .or_else(|| prev.cloned())
+            .unwrap_or_else(|| obj.clone())
+    })
+}
+
+/// Swap the server's targets in `objs` for `server_objs`, leaving the
+/// targets added here where they are.
+fn replace_server_targets(objs: &mut Vec<SkyTarget>, server_objs: Vec<SkyTarget>) {
+    let added = objs.drain(..).filter(|obj| obj.body.is_none()).collect::<Vec<_>>();
+    *objs = server_objs;
+    objs.extend(added);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotMode {
+    Polar,
+    Panorama,
+}
 
 #[component]
-pub fn AppInnerSuccess(objs: Vec<AstronObjectResponse>) -> impl IntoView {
-    let objs = create_rw_signal(objs);
+pub fn AppInnerSuccess(objs: AstronObjectsRw) -> impl IntoView {
+    let state = use_app_state();
+    let projection = create_rw_signal(ProjectionKind::default());
+    let plot_mode = create_rw_signal(PlotMode::Polar);
+    let facing = create_rw_signal(180.0);
+    let fov = create_rw_signal(180.0);
 
-    let selected = create_rw_signal::<Option<AstronObject>>(None);
+    let compass_enabled = create_rw_signal(false);
+    let orientation = use_device_orientation(compass_enabled.into());
+    let heading = Signal::derive(move || orientation.get().map(|o| o.heading));
+
+    // in compass mode the panorama faces wherever the device points
+    create_effect(move |_| {
+        if let Some(heading) = heading.get() {
+            facing.set(heading);
+        }
+    });
+
+    let alerts = use_alerts(objs);
+    let minor_bodies = use_minor_bodies(objs);
+    let custom_targets = use_custom_targets(objs);
 
     let el = create_node_ref::<Div>();
 
@@ -95,31 +122,118 @@ pub fn AppInnerSuccess(objs: Vec<AstronObjectResponse>) -> impl IntoView {
         }
     });
 
-    let polar_plot_view = move || {
-        view! {
+    let polar_plot_view = move || match plot_mode.get() {
+        PlotMode::Polar => view! {
+            <div>
+                <PolarPlot
+                    width={width.get()}
+                    height={width.get()}
+                    radius={radius()}
+                    objs={objs}
+                    projection={projection}
+                    heading={heading}
+                />
+            </div>
+        },
+        PlotMode::Panorama => view! {
             <div>
-                <PolarPlot width={width.get()} height={width.get()} radius={radius()} objs={objs} selected={selected}/>
+                <PanoramaPlot width={width.get()} objs={objs} facing={facing} fov={fov}/>
             </div>
+        },
+    };
+
+    let plot_mode_button = move |mode: PlotMode, label: &'static str| {
+        view! {
+            <button
+                class="py-1 px-2 border border-solid border-line hover:border-line-strong first:rounded-l-md last:rounded-r-md"
+                class=("bg-highlight", move || plot_mode.get() == mode)
+                on:click=move |_| plot_mode.set(mode)
+            >
+                {label}
+            </button>
         }
     };
 
     view! {
-        <div node_ref=el class="flex flex-col content-center justify-center space-y-1 mx-2 sm:mx-0">
+        <div node_ref=el class="flex flex-col content-center justify-center space-y-1 mx-2 sm:mx-0 print:hidden">
             <GeoDateTimeSearch objs={objs}/>
-            <TextDisplay objs={objs} selected={selected}/>
+            <TextDisplay objs={objs}/>
+            <BestTonight objs={objs}/>
+            <AlertSettings alerts={alerts} objs={objs}/>
+            <SatellitePasses/>
+            <MinorBodyList minor_bodies={minor_bodies}/>
+            <CustomTargetList custom_targets={custom_targets}/>
+            <div class="flex items-center space-x-2">
+                <div class="flex">
+                    {plot_mode_button(PlotMode::Polar, "Dome")}
+                    {plot_mode_button(PlotMode::Panorama, "Horizon")}
+                </div>
+                <CompassToggle enabled={compass_enabled}/>
+                <button
+                    class="rounded-md border border-solid py-1 px-2 border-line hover:border-line-strong"
+                    on:click=move |_| {
+                        let _ = window().print();
+                    }
+                >
+                    "Print sheet"
+                </button>
+                <div class="flex">
+                    <button
+                        class="py-1 px-2 border border-solid border-line hover:border-line-strong disabled:opacity-50 rounded-l-md"
+                        title="Undo the last change of place, time, selection or layers"
+                        disabled={move || !state.can_undo()}
+                        on:click=move |_| state.undo()
+                    >
+                        "Undo"
+                    </button>
+                    <button
+                        class="py-1 px-2 border border-solid border-line hover:border-line-strong disabled:opacity-50 rounded-r-md"
+                        title="Redo the last undone change"
+                        disabled={move || !state.can_redo()}
+                        on:click=move |_| state.redo()
+                    >
+                        "Redo"
+                    </button>
+                </div>
+            </div>
+            <CompassGuide objs={objs} orientation={orientation}/>
             {polar_plot_view}
         </div>
+        <ObservingSheet objs={objs}/>
     }
 }
 
 #[component]
 pub fn AppInner(geo_position: Position) -> impl IntoView {
-    let position_time_rw =
-        create_rw_signal::<(Position, DateTime<Utc>)>((geo_position, Utc::now()));
+    provide_app_state(geo_position);
+    provide_satellites();
+    provide_clock();
 
-    provide_context(position_time_rw);
+    let position_time = use_app_state().position_time;
+    let astron_objs = create_resource(move || position_time.get(), get_all_astron_object_data);
 
-    let astron_objs = create_resource(position_time_rw, get_all_astron_object_data);
+    // refetches update the targets in place, so everything built on them
+    // keeps its state, and the last targets stay shown while the next load
+    // and if it fails. `loaded` is whether any load has worked, `error` why
+    // the last one didn't
+    let objs = create_rw_signal(Vec::<SkyTarget>::new());
+    let loaded = create_rw_signal(false);
+    let error = create_rw_signal::<Option<AppError>>(None);
+    create_effect(move |_| {
+        let Some(res) = astron_objs.get() else {
+            return;
+        };
+        match res {
+            Ok(server_objs) => {
+                objs.update(|objs| replace_server_targets(objs, server_objs));
+                if !loaded.get_untracked() {
+                    loaded.set(true);
+                }
+                error.set(None);
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    });
 
     let fallback = move |errors: RwSignal<Errors>| {
         logging::log!("error fallback");
@@ -140,24 +254,38 @@ pub fn AppInner(geo_position: Position) -> impl IntoView {
         }
     };
 
+    // only a failed first load takes the place of the app; later failures
+    // show next to the title
     let success_view = move || {
-        astron_objs.and_then(|data| {
-            view! {
-                <>
-                    <AppInnerSuccess objs={data.clone()}/>
-                </>
-            }
-        })
+        if loaded.get() {
+            return Ok(view! { <AppInnerSuccess objs={objs}/> }.into_view());
+        }
+        match error.get() {
+            Some(e) => Err(e),
+            None => Ok(view! { <div>"Loading..."</div> }.into_view()),
+        }
     };
 
     view! {
         <>
-            <h1 class="text-4xl my-2 mx-2">"Planet Tracker"</h1>
-            <Transition fallback=move || { view! {<div>"Loading..."</div>}}>
-                <ErrorBoundary fallback>
-                    { success_view }
-                </ErrorBoundary>
-            </Transition>
+            <div class="flex flex-wrap items-baseline gap-x-2 my-2 mx-2 print:hidden">
+                <h1 class="text-4xl">"Planet Tracker"</h1>
+                {move || {
+                    let updating = loaded.get() && astron_objs.loading().get();
+                    updating.then(|| view! { <span class="text-muted">"Updating…"</span> })
+                }}
+                {move || {
+                    let e = error.get().filter(|_| loaded.get())?;
+                    Some(view! {
+                        <span class="text-danger" role="alert">
+                            {format!("Couldn't update, showing the last results: {}", e)}
+                        </span>
+                    })
+                }}
+            </div>
+            <ErrorBoundary fallback>
+                { success_view }
+            </ErrorBoundary>
         </>
     }
 }
@@ -165,7 +293,10 @@ pub fn AppInner(geo_position: Position) -> impl IntoView {
 #[component]
 pub fn App() -> impl IntoView {
     provide_meta_context();
+    provide_service_worker();
+    provide_gazetteer();
     let geo_position = Position::from_browser().expect("Can get geolocation");
+    provide_theme(geo_position.into());
 
     create_effect(move |_| {
         log!("geo_position={:?}", geo_position.get());
@@ -174,6 +305,9 @@ pub fn App() -> impl IntoView {
     view! {
         <Stylesheet id="leptos" href="/pkg/tailwind.css"/>
         <div class="my-0 mx-auto max-w-3xl">
+            <div class="flex justify-end mx-2 mt-2 sm:mx-0 print:hidden">
+                <ThemeSelect/>
+            </div>
             <Show
                 when=move || geo_position.get().is_some()
                 fallback=move || view ! { <div>"Waiting for geo location"</div>}
@@ -184,3 +318,52 @@ pub fn App() -> impl IntoView {
 
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::models::{AstronObjectResponse, TargetKind};
+
+    fn planet(name: AstronObject, el: f64) -> SkyTarget {
+        let when = NaiveDate::from_ymd_opt(2024, 3, 20)
+            .unwrap()
+            .and_hms_opt(21, 0, 0)
+            .unwrap();
+        SkyTarget::from(AstronObjectResponse {
+            name,
+            magnitude: 0.0,
+            size: 10.0,
+            az: 1.0,
+            el,
+            ra: 1.0,
+            dec: 0.2,
+            setting_time: when,
+            rising_time: when,
+            when,
+        })
+    }
+
+    #[test]
+    fn refetched_targets_replace_the_servers_only() {
+        let vega = SkyTarget {
+            id: TargetId::new(TargetKind::Custom, "Vega"),
+            kind: TargetKind::Custom,
+            name: "Vega".to_string(),
+            body: None,
+            ..planet(AstronObject::Mars, 0.5)
+        };
+        let mut objs = vec![
+            planet(AstronObject::Sun, -0.2),
+            planet(AstronObject::Mars, 0.3),
+            vega.clone(),
+        ];
+        let refetched = vec![
+            planet(AstronObject::Sun, -0.1),
+            planet(AstronObject::Mars, 0.4),
+        ];
+        replace_server_targets(&mut objs, refetched.clone());
+        assert_eq!(objs, [refetched[0].clone(), refetched[1].clone(), vega]);
+    }
+}
@@ -0,0 +1,313 @@
+//! Components mounted in a headless browser, run with
+//! `wasm-pack test --headless --firefox` (or `--chrome`).
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use leptos::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::{Element, HtmlElement, KeyboardEvent, KeyboardEventInit, MouseEvent, MouseEventInit};
+
+use crate::{
+    clock::provide_clock,
+    components::{GeoDateTimeSearch, PolarPlot, TextDisplay},
+    gazetteer::provide_gazetteer,
+    models::{AstronObject, AstronObjectResponse, Position, SkyTarget, TargetId, TargetKind},
+    projection::ProjectionKind,
+    satellites::provide_empty_satellites,
+    state::{Action, AppState, Layer},
+    theme::provide_theme,
+};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn when() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, 20)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+}
+
+fn greenwich() -> Position {
+    Position {
+        lat: 51.4769,
+        lon: -0.0005,
+        elevation: 0.0,
+    }
+}
+
+fn planet(name: AstronObject, az: f64, el: f64, magnitude: f64) -> SkyTarget {
+    SkyTarget::from(AstronObjectResponse {
+        name,
+        magnitude,
+        size: 30.0,
+        az: az.to_radians(),
+        el: el.to_radians(),
+        ra: 1.0,
+        dec: 0.2,
+        setting_time: when() + Duration::hours(3),
+        rising_time: when() + Duration::hours(15),
+        when: when(),
+    })
+}
+
+/// the Sun below the horizon, two planets and a custom target
+fn fixture() -> Vec<SkyTarget> {
+    let jupiter = planet(AstronObject::Jupiter, 260.0, 25.0, -2.1);
+    let vega = SkyTarget {
+        id: TargetId::new(TargetKind::Custom, "Vega"),
+        kind: TargetKind::Custom,
+        name: "Vega".to_string(),
+        color: None,
+        body: None,
+        magnitude: 0.0,
+        size: 0.0,
+        az: 20f64.to_radians(),
+        el: 30f64.to_radians(),
+        transit_time: Some(when() + Duration::hours(8)),
+        ..jupiter.clone()
+    };
+    vec![
+        planet(AstronObject::Sun, 290.0, -10.0, -26.7),
+        planet(AstronObject::Moon, 200.0, 40.0, -11.0),
+        jupiter,
+        vega,
+    ]
+}
+
+/// Mount `view` in a fresh element on the page, with the contexts the app
+/// provides around it, but no satellite elements so nothing is fetched.
+/// The state is returned to watch what the view does.
+fn mount<N: IntoView + 'static>(view: impl FnOnce() -> N + 'static) -> (Element, AppState) {
+    let container = document().create_element("div").unwrap();
+    document().body().unwrap().append_child(&container).unwrap();
+    let time = DateTime::<Utc>::from_naive_utc_and_offset(when(), Utc);
+    let state = AppState::new(greenwich(), time);
+    mount_to(
+        container.clone().unchecked_into::<HtmlElement>(),
+        move || {
+            let position = greenwich();
+            provide_context(state);
+            provide_theme(Signal::derive(move || Some(position.clone())));
+            provide_gazetteer();
+            provide_empty_satellites();
+            provide_clock();
+            view()
+        },
+    );
+    (container, state)
+}
+
+fn query(container: &Element, selector: &str) -> Element {
+    container
+        .query_selector(selector)
+        .unwrap()
+        .unwrap_or_else(|| panic!("nothing matches {}", selector))
+}
+
+fn count(container: &Element, selector: &str) -> u32 {
+    container.query_selector_all(selector).unwrap().length()
+}
+
+fn text(element: &Element) -> String {
+    element
+        .text_content()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// click that bubbles up to leptos' delegated listeners, which `click()`
+/// can't do for SVG elements
+fn click(element: &Element) {
+    let mut init = MouseEventInit::new();
+    init.bubbles(true);
+    let event = MouseEvent::new_with_mouse_event_init_dict("click", &init).unwrap();
+    element.dispatch_event(&event).unwrap();
+}
+
+fn key(element: &Element, key: &str) {
+    let mut init = KeyboardEventInit::new();
+    init.bubbles(true).key(key);
+    let event = KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init).unwrap();
+    element.dispatch_event(&event).unwrap();
+}
+
+#[wasm_bindgen_test]
+fn text_display_lists_every_target() {
+    let objs = create_rw_signal(fixture());
+    let (container, _) = mount(move || view! { <TextDisplay objs=objs/> });
+
+    assert_eq!(count(&container, "thead th"), 7);
+    assert_eq!(count(&container, "tbody tr"), 4);
+    let names = (1..=4)
+        .map(|i| text(&query(&container, &format!("tbody tr:nth-child({}) td", i))))
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Sun", "Moon", "Jupiter", "Vega"]);
+    assert_eq!(
+        query(&container, "tbody tr:nth-child(4) td")
+            .get_attribute("title")
+            .unwrap(),
+        "Custom target"
+    );
+    // direction and elevation
+    assert_eq!(
+        text(&query(&container, "tbody tr:nth-child(3) td:nth-child(2)")),
+        "W"
+    );
+    assert_eq!(
+        text(&query(&container, "tbody tr:nth-child(3) td:nth-child(3)")),
+        "25.00°"
+    );
+    // only the custom target has a transit time
+    assert_eq!(
+        text(&query(&container, "tbody tr:nth-child(3) td:nth-child(6)")),
+        "-"
+    );
+    assert_ne!(
+        text(&query(&container, "tbody tr:nth-child(4) td:nth-child(6)")),
+        "-"
+    );
+
+    // targets added later show up too
+    objs.update(|objs| objs.push(planet(AstronObject::Mars, 100.0, 5.0, 1.2)));
+    assert_eq!(count(&container, "tbody tr"), 5);
+}
+
+#[wasm_bindgen_test]
+fn text_display_selects_rows() {
+    let objs = create_rw_signal(fixture());
+    let (container, state) = mount(move || view! { <TextDisplay objs=objs/> });
+
+    let jupiter = query(&container, "tbody tr:nth-child(3)");
+    let background = jupiter
+        .unchecked_ref::<HtmlElement>()
+        .style()
+        .get_property_value("background-color")
+        .unwrap();
+    click(&jupiter);
+    assert_eq!(
+        state.selected.get_untracked(),
+        Some(TargetId::from(&AstronObject::Jupiter))
+    );
+    let highlighted = jupiter
+        .unchecked_ref::<HtmlElement>()
+        .style()
+        .get_property_value("background-color")
+        .unwrap();
+    assert_ne!(highlighted, background);
+
+    click(&query(&container, "tbody tr:nth-child(4)"));
+    assert_eq!(
+        state.selected.get_untracked(),
+        Some(TargetId::new(TargetKind::Custom, "Vega"))
+    );
+
+    click(&query(&container, "tbody tr:nth-child(4)"));
+    assert_eq!(state.selected.get_untracked(), None);
+
+    state.undo();
+    assert_eq!(
+        state.selected.get_untracked(),
+        Some(TargetId::new(TargetKind::Custom, "Vega"))
+    );
+}
+
+#[wasm_bindgen_test]
+fn text_display_updates_rows_in_place() {
+    let objs = create_rw_signal(fixture());
+    let (container, state) = mount(move || view! { <TextDisplay objs=objs/> });
+    state.dispatch(Action::Select(Some(TargetId::from(&AstronObject::Jupiter))));
+    let jupiter = query(&container, "tbody tr:nth-child(3)");
+
+    // as after a refetch: same targets, new positions
+    objs.update(|objs| objs[2].el = 10f64.to_radians());
+    assert_eq!(
+        text(&query(&container, "tbody tr:nth-child(3) td:nth-child(3)")),
+        "10.00°"
+    );
+    assert!(jupiter.is_same_node(Some(&query(&container, "tbody tr:nth-child(3)"))));
+    assert_eq!(
+        state.selected.get_untracked(),
+        Some(TargetId::from(&AstronObject::Jupiter))
+    );
+}
+
+#[wasm_bindgen_test]
+fn polar_plot_marks_and_selects_targets() {
+    let objs = create_rw_signal(fixture());
+    let (container, state) = mount(move || {
+        view! {
+            <PolarPlot
+                width=400
+                height=400
+                radius=160
+                objs=objs
+                projection=create_rw_signal(ProjectionKind::default())
+                heading=Signal::derive(|| None)
+            />
+        }
+    });
+    assert!(!text(&container).contains("LST"));
+    state.dispatch(Action::ToggleLayer(Layer::Meridian));
+
+    assert_eq!(count(&container, "circle[data-object]"), 4);
+    assert!(text(&container).contains("LST"));
+    // the Sun is below the horizon, so it's faded
+    assert_eq!(
+        query(&container, r#"circle[data-object="sun"]"#)
+            .get_attribute("opacity")
+            .unwrap(),
+        "0.4"
+    );
+    assert_eq!(
+        query(&container, r#"circle[data-object="jupiter"]"#)
+            .get_attribute("opacity")
+            .unwrap(),
+        "1.0"
+    );
+
+    let vega = query(&container, r#"circle[data-object="custom:vega"]"#);
+    click(&vega);
+    assert_eq!(
+        state.selected.get_untracked(),
+        Some(TargetId::new(TargetKind::Custom, "Vega"))
+    );
+    assert_eq!(text(&query(&container, "h3")), "Vega");
+
+    key(&query(&container, "svg[role=application]"), "Escape");
+    assert_eq!(state.selected.get_untracked(), None);
+
+    objs.update(|objs| objs.retain(|obj| obj.kind != TargetKind::Custom));
+    assert_eq!(count(&container, "circle[data-object]"), 3);
+}
+
+#[wasm_bindgen_test]
+fn geo_date_time_search_describes_the_position() {
+    window().local_storage().unwrap().unwrap().clear().unwrap();
+    let objs = create_rw_signal(fixture());
+    let (container, state) = mount(move || view! { <GeoDateTimeSearch objs=objs/> });
+
+    assert_eq!(count(&container, "input[role=combobox]"), 1);
+    let summary = text(&container);
+    assert!(summary.contains("Showing Ephemerides for"), "{}", summary);
+    assert!(summary.contains("51.48°N, 0.00°W"), "{}", summary);
+    assert!(summary.contains("Wednesday, 20 March 2024"), "{}", summary);
+
+    // the fixture is in the past, so there's a choice of times
+    assert_eq!(text(&query(&container, "select option")), "Now");
+
+    click(&query(&container, "button[title^=Switch]"));
+    let summary = text(&container);
+    assert!(
+        summary.contains("51° 28′ 37″ N, 0° 00′ 02″ W"),
+        "{}",
+        summary
+    );
+
+    // picking a time goes through the store, so it can be undone
+    let start = state.position_time.get_untracked().1;
+    click(&query(&container, "select option"));
+    assert_ne!(state.position_time.get_untracked().1, start);
+    state.undo();
+    assert_eq!(state.position_time.get_untracked().1, start);
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use leptos::*;
+use leptos_use::use_interval_fn;
+
+use crate::{
+    coords::{gmst, julian_date, local_sidereal_time},
+    models::Position,
+    state::use_app_state,
+};
+
+/// how close to the wall clock a chosen time has to be to count as now
+const LIVE_TOLERANCE_SECONDS: i64 = 60;
+const TICK_MILLISECONDS: u64 = 1000;
+
+/// The same instant in the time systems observers use. Sidereal times are
+/// in radians.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSystems {
+    pub utc: DateTime<Utc>,
+    pub jd: f64,
+    pub gmst: f64,
+    pub lst: f64,
+}
+
+impl TimeSystems {
+    pub fn new(utc: DateTime<Utc>, position: &Position) -> Self {
+        let jd = julian_date(&utc.naive_utc());
+        Self {
+            utc,
+            jd,
+            gmst: gmst(jd),
+            lst: local_sidereal_time(jd, position.lon.to_radians()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    /// whether the time being shown is now, rather than one picked by the user
+    pub live: Memo<bool>,
+    /// ticks every second while live, and otherwise stays at the picked time
+    pub time_systems: Memo<TimeSystems>,
+}
+
+pub fn use_clock() -> Clock {
+    use_context::<Clock>()
+        .expect("Clock missing from context, provide_clock() must run in an ancestor")
+}
+
+/// Provide the `Clock` context for the position and time in the `AppState`.
+pub fn provide_clock() {
+    let position_time = use_app_state().position_time;
+    let now = create_rw_signal(Utc::now());
+    let _ = use_interval_fn(move || now.set(Utc::now()), TICK_MILLISECONDS);
+
+    // decided when the time is picked, so live mode doesn't lapse as the
+    // clock runs on
+    let live = create_memo(move |_| {
+        let (_, when) = position_time.get();
+        (when - Utc::now()).num_seconds().abs() < LIVE_TOLERANCE_SECONDS
+    });
+
+    let time_systems = create_memo(move |_| {
+        let (position, when) = position_time.get();
+        let utc = if live.get() { now.get() } else { when };
+        TimeSystems::new(utc, &position)
+    });
+
+    provide_context(Clock { live, time_systems });
+}
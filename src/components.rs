@@ -1,7 +1,31 @@
 pub mod polar_plot;
 pub mod text_display;
 pub mod geo_date_time_search;
+pub mod detail_panel;
+pub mod panorama_plot;
+pub mod compass;
+pub mod theme_select;
+pub mod plot_export;
+pub mod observing_sheet;
+pub mod best_tonight;
+pub mod alert_settings;
+pub mod satellite_passes;
+pub mod minor_body_list;
+pub mod custom_target_list;
+pub mod time_systems_panel;
 
 pub use polar_plot::PolarPlot;
 pub use text_display::TextDisplay;
-pub use geo_date_time_search::GeoDateTimeSearch;
\ No newline at end of file
+pub use geo_date_time_search::GeoDateTimeSearch;
+pub use detail_panel::DetailPanel;
+pub use panorama_plot::PanoramaPlot;
+pub use compass::{CompassGuide, CompassToggle};
+pub use theme_select::ThemeSelect;
+pub use plot_export::PlotExport;
+pub use observing_sheet::ObservingSheet;
+pub use best_tonight::BestTonight;
+pub use alert_settings::AlertSettings;
+pub use satellite_passes::SatellitePasses;
+pub use minor_body_list::MinorBodyList;
+pub use custom_target_list::CustomTargetList;
+pub use time_systems_panel::TimeSystemsPanel;
\ No newline at end of file
@@ -0,0 +1,199 @@
+use enum_iterator::all;
+use leptos::*;
+use web_sys::NotificationPermission;
+
+use crate::{
+    alerts::{background_alerts_supported, AlertEvent, AlertRule, Alerts},
+    models::{SkyTarget, TargetId, TargetKind},
+    satellites::{use_satellites, Satellite},
+    AstronObjectsRw,
+};
+
+const INPUT_CLASS: &str = "rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none";
+
+/// (id, name) of everything an alert can be about: the listed targets, then
+/// the satellites
+fn alert_targets(objs: &[SkyTarget], satellites: &[Satellite]) -> Vec<(TargetId, String)> {
+    let mut targets = objs
+        .iter()
+        .map(|obj| (obj.id.clone(), obj.name.clone()))
+        .collect::<Vec<_>>();
+    for satellite in satellites {
+        let id = TargetId::new(TargetKind::Satellite, &satellite.name);
+        if !targets.iter().any(|(other, _)| *other == id) {
+            targets.push((id, satellite.name.clone()));
+        }
+    }
+    targets
+}
+
+#[component]
+fn AlertRuleRow(rule: AlertRule, alerts: Alerts, objs: AstronObjectsRw) -> impl IntoView {
+    let id = rule.id;
+    let satellites = use_satellites().satellites;
+    let update = move |f: &dyn Fn(&mut AlertRule)| {
+        alerts.set_rules.update(|rules| {
+            if let Some(rule) = rules.iter_mut().find(|rule| rule.id == id) {
+                f(rule);
+            }
+        })
+    };
+    let initial_object = rule.object.clone();
+    let object = create_memo(move |_| {
+        alerts.rules.with(|rules| {
+            rules
+                .iter()
+                .find(|rule| rule.id == id)
+                .map(|rule| rule.object.clone())
+                .unwrap_or_else(|| initial_object.clone())
+        })
+    });
+    let event = move || {
+        alerts
+            .rules
+            .with(|rules| rules.iter().find(|rule| rule.id == id).map(|rule| rule.event))
+    };
+
+    let object_options = move || {
+        let current = object.get();
+        let mut targets =
+            objs.with(|objs| satellites.with(|satellites| alert_targets(objs, satellites)));
+        // a removed custom target or a satellite no longer in the elements
+        if !targets.iter().any(|(id, _)| *id == current) {
+            targets.push((current.clone(), current.0.clone()));
+        }
+        targets
+            .into_iter()
+            .map(|(id, name)| {
+                let selected = id == current;
+                view! { <option value={id.0} selected={selected}>{name}</option> }
+            })
+            .collect_view()
+    };
+    let event_options = move || {
+        let current = object.get();
+        all::<AlertEvent>()
+            .enumerate()
+            .filter(|(_, e)| e.applies_to(&current))
+            .map(|(i, e)| {
+                view! {
+                    <option value={i} selected={move || event() == Some(e)}>{e.to_string()}</option>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <li class="flex flex-wrap items-center gap-x-2 gap-y-1">
+            <input
+                type="checkbox"
+                aria-label="Enabled"
+                prop:checked={rule.enabled}
+                on:change=move |evt| {
+                    let enabled = event_target_checked(&evt);
+                    update(&|rule| rule.enabled = enabled)
+                }
+            />
+            <input
+                type="number"
+                min="0"
+                class={format!("w-16 {}", INPUT_CLASS)}
+                prop:value={rule.minutes_before}
+                on:change=move |evt| {
+                    if let Ok(minutes) = event_target_value(&evt).parse::<i64>() {
+                        update(&|rule| rule.minutes_before = minutes.max(0))
+                    }
+                }
+            />
+            "minutes before"
+            <select
+                class={INPUT_CLASS}
+                on:change=move |evt| {
+                    let object = TargetId(event_target_value(&evt));
+                    update(&|rule| rule.set_object(object.clone()))
+                }
+            >
+                {object_options}
+            </select>
+            <select
+                class={INPUT_CLASS}
+                on:change=move |evt| {
+                    let event = event_target_value(&evt)
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| all::<AlertEvent>().nth(i));
+                    if let Some(event) = event {
+                        update(&|rule| rule.event = event)
+                    }
+                }
+            >
+                {event_options}
+            </select>
+            <button
+                aria-label="Remove alert"
+                class="px-2"
+                on:click=move |_| alerts.set_rules.update(|rules| rules.retain(|rule| rule.id != id))
+            >
+                "×"
+            </button>
+        </li>
+    }
+}
+
+/// Settings panel for rise, set and satellite pass notifications.
+#[component]
+pub fn AlertSettings(alerts: Alerts, objs: AstronObjectsRw) -> impl IntoView {
+    let add_rule = move |_| {
+        alerts.set_rules.update(|rules| {
+            let id = rules.iter().map(|rule| rule.id + 1).max().unwrap_or(0);
+            rules.push(AlertRule::new(id));
+        });
+        if alerts.permission.get_untracked() == NotificationPermission::Default {
+            alerts.request_permission();
+        }
+    };
+
+    let permission_note = move || match alerts.permission.get() {
+        NotificationPermission::Granted => None,
+        NotificationPermission::Denied => Some(view! {
+            <div class="text-danger">"Notifications are blocked for this site in the browser settings"</div>
+        }.into_view()),
+        _ => Some(view! {
+            <button
+                class="rounded-md border border-solid py-1 px-2 border-line hover:border-line-strong"
+                on:click=move |_| alerts.request_permission()
+            >
+                "Allow notifications"
+            </button>
+        }.into_view()),
+    };
+
+    view! {
+        <details>
+            <summary class="font-bold cursor-pointer">"Alerts"</summary>
+            <div class="flex flex-col gap-y-1 my-1">
+                {permission_note}
+                {(!background_alerts_supported()).then(|| view! {
+                    <div class="text-sm text-muted">
+                        "This browser can only fire alerts from this page, so keep the tab open. Background tabs may fire them late."
+                    </div>
+                })}
+                <ul class="flex flex-col gap-y-1">
+                    <For
+                        each=move || alerts.rules.get()
+                        key=|rule| rule.id
+                        children=move |rule: AlertRule| view! { <AlertRuleRow rule=rule alerts=alerts objs=objs/> }
+                    />
+                </ul>
+                <div>
+                    <button
+                        class="rounded-md border border-solid py-1 px-2 border-line hover:border-line-strong"
+                        on:click=add_rule
+                    >
+                        "Add alert"
+                    </button>
+                </div>
+            </div>
+        </details>
+    }
+}
@@ -0,0 +1,46 @@
+use leptos::*;
+
+use crate::{
+    recommend::best_tonight,
+    state::{use_app_state, Action},
+    AstronObjectsRw,
+};
+
+/// Ranked list of what's worth looking at tonight, with reasons. Clicking
+/// an entry selects the object.
+#[component]
+pub fn BestTonight(objs: AstronObjectsRw) -> impl IntoView {
+    let state = use_app_state();
+
+    let recommendations = create_memo(move |_| {
+        let (position, _) = state.position_time.get();
+        objs.with(|objs| best_tonight(objs, &position))
+    });
+
+    let items = move || {
+        recommendations
+            .get()
+            .into_iter()
+            .map(|rec| {
+                let id = rec.id.clone();
+                view! {
+                    <li class="cursor-pointer hover:underline" on:click=move |_| state.dispatch(Action::ToggleSelected(id.clone()))>
+                        {rec.summary()}
+                    </li>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div>
+            <h2 class="font-bold">"Best tonight"</h2>
+            <Show
+                when=move || !recommendations.with(|r| r.is_empty())
+                fallback=|| view! { <div class="text-muted">"Nothing well placed in darkness tonight"</div> }
+            >
+                <ol class="list-decimal list-inside">{items}</ol>
+            </Show>
+        </div>
+    }
+}
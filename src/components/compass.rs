@@ -0,0 +1,90 @@
+use leptos::*;
+
+use crate::{
+    orientation::{relative_bearing, request_orientation_permission, DeviceOrientation},
+    state::use_app_state,
+    theme::use_theme,
+    utils::rad2deg,
+    AstronObjectsRw,
+};
+
+/// how close to the target counts as pointing at it, in degrees
+const ON_TARGET: f64 = 5.0;
+
+#[component]
+pub fn CompassToggle(enabled: RwSignal<bool>) -> impl IntoView {
+    let (error, set_error) = create_signal::<Option<String>>(None);
+
+    let on_click = move |_| {
+        if enabled.get_untracked() {
+            enabled.set(false);
+            return;
+        }
+        spawn_local(async move {
+            match request_orientation_permission().await {
+                Ok(()) => {
+                    set_error.set(None);
+                    enabled.set(true);
+                }
+                Err(err) => set_error.set(Some(err.to_string())),
+            }
+        });
+    };
+
+    view! {
+        <button
+            class="py-1 px-2 rounded-md border border-solid border-line hover:border-line-strong"
+            class=("bg-highlight", move || enabled.get())
+            on:click=on_click
+        >
+            {move || if enabled.get() { "Compass on" } else { "Compass off" }}
+        </button>
+        {move || error.get().map(|err| view! { <span class="text-sm text-danger">{err}</span> })}
+    }
+}
+
+/// Arrow and hint pointing from where the device faces to the selected object.
+#[component]
+pub fn CompassGuide(
+    objs: AstronObjectsRw,
+    orientation: Signal<Option<DeviceOrientation>>,
+) -> impl IntoView {
+    let theme = use_theme();
+    let state = use_app_state();
+
+    move || {
+        let orientation = orientation.get()?;
+        let id = state.selected.get()?;
+        let obj = objs.with(|objs| objs.iter().find(|o| o.id == id).cloned())?;
+
+        let turn = relative_bearing(orientation.heading, rad2deg(obj.az));
+        let tilt = rad2deg(obj.el) - orientation.altitude;
+        let hint = if turn.abs() < ON_TARGET && tilt.abs() < ON_TARGET {
+            format!("{} is straight ahead", obj.name)
+        } else {
+            format!(
+                "Turn {:.0}° {}, look {} {:.0}°",
+                turn.abs(),
+                if turn > 0.0 { "right" } else { "left" },
+                if tilt > 0.0 { "up" } else { "down" },
+                tilt.abs()
+            )
+        };
+
+        let palette = theme.palette();
+        Some(view! {
+            <div class="flex items-center space-x-2">
+                <svg width="48" height="48" viewBox="-24 -24 48 48">
+                    <circle r="22" fill="none" stroke={palette.ink} stroke-width="1"/>
+                    <path
+                        d="M0 -18 L10 12 L0 6 L-10 12 Z"
+                        fill={obj.get_color(theme.kind.get())}
+                        stroke={palette.ink}
+                        transform={format!("rotate({})", turn)}
+                    />
+                </svg>
+                <span>{hint}</span>
+            </div>
+        })
+    }
+}
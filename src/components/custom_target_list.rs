@@ -0,0 +1,128 @@
+use leptos::*;
+
+use crate::{
+    coords::{format_dms, format_hms, parse_dms, parse_hms},
+    custom_targets::{CustomTarget, CustomTargets},
+    errors::AppError,
+};
+
+const INPUT_CLASS: &str = "rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none";
+
+/// parse a form field as a number, or zero if it was left `optional` and empty
+fn parse_field(label: &str, text: &str, optional: bool) -> Result<f64, AppError> {
+    let text = text.trim();
+    if text.is_empty() && optional {
+        return Ok(0.0);
+    }
+    text.parse::<f64>()
+        .map_err(|_| AppError::ParseError(format!("{} must be a number", label)))
+}
+
+/// Panel for adding stars and other fixed targets by their J2000 position,
+/// and removing them again.
+#[component]
+pub fn CustomTargetList(custom_targets: CustomTargets) -> impl IntoView {
+    let name = create_rw_signal(String::new());
+    let ra = create_rw_signal(String::new());
+    let dec = create_rw_signal(String::new());
+    let pm_ra = create_rw_signal(String::new());
+    let pm_dec = create_rw_signal(String::new());
+    let magnitude = create_rw_signal("6".to_string());
+
+    let add = move |evt: web_sys::SubmitEvent| {
+        evt.prevent_default();
+        let target = (|| {
+            Ok::<_, AppError>(CustomTarget {
+                name: name.get_untracked().trim().to_string(),
+                ra: parse_hms(&ra.get_untracked())?,
+                dec: parse_dms(&dec.get_untracked())?,
+                pm_ra: parse_field("Proper motion in RA", &pm_ra.get_untracked(), true)?,
+                pm_dec: parse_field("Proper motion in Dec", &pm_dec.get_untracked(), true)?,
+                magnitude: parse_field("Magnitude", &magnitude.get_untracked(), false)?,
+            })
+        })();
+        match target {
+            Ok(target) => custom_targets.add(target),
+            Err(e) => return custom_targets.error.set(Some(e)),
+        }
+        if custom_targets.error.get_untracked().is_none() {
+            for field in [name, ra, dec, pm_ra, pm_dec] {
+                field.set(String::new());
+            }
+        }
+    };
+
+    let field = move |label: &'static str, placeholder: &'static str, value: RwSignal<String>| {
+        view! {
+            <label class="flex flex-col text-sm">
+                {label}
+                <input
+                    type="text"
+                    placeholder={placeholder}
+                    class={INPUT_CLASS}
+                    prop:value={move || value.get()}
+                    on:input=move |evt| value.set(event_target_value(&evt))
+                />
+            </label>
+        }
+    };
+
+    view! {
+        <details>
+            <summary class="font-bold cursor-pointer">"Custom targets"</summary>
+            <div class="flex flex-col gap-y-1 my-1">
+                <ul class="flex flex-col gap-y-1">
+                    <For
+                        each=move || custom_targets.targets.get()
+                        key=|target| target.name.clone()
+                        children=move |target: CustomTarget| {
+                            let remove_name = target.name.clone();
+                            view! {
+                                <li class="flex items-center gap-x-2">
+                                    {target.name.clone()}
+                                    <span class="text-sm text-muted">
+                                        {format!("{} {}", format_hms(target.ra), format_dms(target.dec))}
+                                    </span>
+                                    <button
+                                        aria-label="Remove"
+                                        class="px-2"
+                                        on:click=move |_| custom_targets.remove(&remove_name)
+                                    >
+                                        "×"
+                                    </button>
+                                </li>
+                            }
+                        }
+                    />
+                </ul>
+                <form class="flex flex-col gap-y-1" on:submit=add>
+                    <label class="flex flex-col text-sm">
+                        "Name"
+                        <input
+                            type="text"
+                            class={INPUT_CLASS}
+                            prop:value={move || name.get()}
+                            on:input=move |evt| name.set(event_target_value(&evt))
+                        />
+                    </label>
+                    <div class="grid grid-cols-2 gap-x-2 gap-y-1">
+                        {field("RA (J2000)", "05 55 10.3", ra)}
+                        {field("Dec (J2000)", "+07 24 25", dec)}
+                        {field("Proper motion RA (mas/yr)", "optional", pm_ra)}
+                        {field("Proper motion Dec (mas/yr)", "optional", pm_dec)}
+                        {field("Magnitude", "6", magnitude)}
+                    </div>
+                    <div>
+                        <button
+                            type="submit"
+                            class="rounded-md border border-solid py-1 px-2 border-line hover:border-line-strong"
+                        >
+                            "Add"
+                        </button>
+                    </div>
+                </form>
+                {move || custom_targets.error.get().map(|e| view! { <div class="text-danger">{e.to_string()}</div> })}
+            </div>
+        </details>
+    }
+}
@@ -0,0 +1,228 @@
+use chrono::{Duration, NaiveDateTime};
+use leptos::{ev, html::Div, svg::Svg, *};
+use leptos_use::{use_event_listener, use_window};
+
+use crate::{
+    components::polar_plot::ViewBoxRw,
+    constellations::constellation,
+    coords::{
+        angular_separation, equatorial_to_galactic, equatorial_to_horizontal, format_dms,
+        format_hms, julian_date, local_sidereal_time, precess, J2000,
+    },
+    models::{AstronObject, Position, SkyTarget},
+    state::{use_app_state, Action},
+    utils::{deg2cardinal, format_crossing, naive_utc_to_local, rad2deg},
+    AstronObjectsRw,
+};
+
+const PANEL_GAP: f64 = 12.0;
+const VIEWPORT_MARGIN: f64 = 8.0;
+
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Time during the coming night at which `obj` stands highest, along with
+/// its elevation at that time. The night runs from sunset, or from now if
+/// the Sun is already down, to the next sunrise. RA and Dec are held fixed,
+/// which is good enough for planets over a single night.
+fn best_viewing_time(
+    obj: &SkyTarget,
+    sun: &SkyTarget,
+    position: &Position,
+) -> Option<(NaiveDateTime, f64)> {
+    let start = if sun.el < 0.0 { sun.when } else { sun.setting_time? };
+    // the whole day ahead, if the Sun doesn't rise in it
+    let end = sun.rising_time.unwrap_or(sun.when + Duration::days(1));
+    let (lat, lon) = (position.lat.to_radians(), position.lon.to_radians());
+
+    let mut best: Option<(NaiveDateTime, f64)> = None;
+    let mut t = start;
+    while t <= end {
+        let lst = local_sidereal_time(julian_date(&t), lon);
+        let (_, el) = equatorial_to_horizontal(obj.ra, obj.dec, lat, lst);
+        if best.is_none_or(|(_, best_el)| el > best_el) {
+            best = Some((t, el));
+        }
+        t += Duration::minutes(10);
+    }
+    best.filter(|(_, el)| *el > 0.0)
+}
+
+fn detail_rows(
+    obj: &SkyTarget,
+    objs: &[SkyTarget],
+    position: &Position,
+) -> Vec<(&'static str, String)> {
+    let formatter = "%a %H:%M";
+    let az = rad2deg(obj.az);
+    let (ra_j2000, dec_j2000) = precess(obj.ra, obj.dec, julian_date(&obj.when), J2000);
+    let (l, b) = equatorial_to_galactic(ra_j2000, dec_j2000);
+    let mut rows = vec![
+        ("Type", obj.kind.to_string()),
+        ("Right ascension", format_hms(obj.ra)),
+        ("Declination", format_dms(obj.dec)),
+        ("Galactic", format!("l {:.2}°, b {:+.2}°", rad2deg(l), rad2deg(b))),
+        ("Azimuth", format!("{:.2}° ({:#})", az, deg2cardinal(az))),
+        ("Elevation", format!("{:+.2}°", rad2deg(obj.el))),
+        ("Magnitude", format!("{:+.2} mag", obj.magnitude)),
+        ("Apparent size", format!("{:.1}″", obj.size)),
+        ("Rising time", format_crossing(obj, obj.rising_time, formatter)),
+        ("Setting time", format_crossing(obj, obj.setting_time, formatter)),
+    ];
+    if let Some(transit_time) = obj.transit_time {
+        rows.push((
+            "Transit time",
+            naive_utc_to_local(&transit_time).format(formatter).to_string(),
+        ));
+    }
+    rows.push((
+        "Computed for",
+        naive_utc_to_local(&obj.when).format("%Y-%m-%d %H:%M:%S").to_string(),
+    ));
+    // the table only covers the band along the ecliptic, so stars and
+    // comets further off get no row rather than a blank one
+    if let Some(name) = constellation(obj.ra, obj.dec, julian_date(&obj.when)) {
+        rows.push(("Constellation", name.to_string()));
+    }
+
+    if !obj.is(&AstronObject::Moon) {
+        if let Some(moon) = objs.iter().find(|o| o.is(&AstronObject::Moon)) {
+            let separation = angular_separation(obj.ra, obj.dec, moon.ra, moon.dec);
+            rows.push(("Separation from Moon", format!("{:.1}°", rad2deg(separation))));
+        }
+    }
+
+    let until = |time: Option<NaiveDateTime>| {
+        time.map_or_else(
+            || obj.missing_crossing().to_string(),
+            |time| format_duration(time - obj.when),
+        )
+    };
+    if obj.el > 0.0 {
+        rows.push(("Sets in", until(obj.setting_time)));
+    } else {
+        rows.push(("Rises in", until(obj.rising_time)));
+    }
+
+    if !obj.is(&AstronObject::Sun) {
+        let best = objs
+            .iter()
+            .find(|o| o.is(&AstronObject::Sun))
+            .and_then(|sun| best_viewing_time(obj, sun, position))
+            .map(|(t, el)| {
+                format!(
+                    "{} at {:.0}°",
+                    naive_utc_to_local(&t).format("%H:%M"),
+                    rad2deg(el)
+                )
+            })
+            .unwrap_or("Not visible tonight".to_string());
+        rows.push(("Best viewing tonight", best));
+    }
+    rows
+}
+
+/// Detail panel for the selected object, anchored next to its marker in `plot`.
+#[component]
+pub fn DetailPanel(
+    objs: AstronObjectsRw,
+    plot: NodeRef<Svg>,
+    view_box: ViewBoxRw,
+    heading: Signal<Option<f64>>,
+) -> impl IntoView {
+    let state = use_app_state();
+    let selected = state.selected;
+    let panel_ref = create_node_ref::<Div>();
+    // (left, top) in viewport coordinates
+    let anchor = create_rw_signal::<Option<(f64, f64)>>(None);
+
+    let reposition = move || {
+        let marker = selected.get_untracked().and_then(|id| {
+            // ids can hold any name, so quote them for the selector
+            let id = id.0.replace('\\', "\\\\").replace('"', "\\\"");
+            plot.get_untracked()?
+                .query_selector(&format!("[data-object=\"{}\"]", id))
+                .ok()
+                .flatten()
+        });
+        let (Some(marker), Some(panel)) = (marker, panel_ref.get_untracked()) else {
+            anchor.set(None);
+            return;
+        };
+        let rect = marker.get_bounding_client_rect();
+        let viewport_width = window().inner_width().ok().and_then(|w| w.as_f64()).unwrap_or(0.0);
+        let viewport_height = window().inner_height().ok().and_then(|h| h.as_f64()).unwrap_or(0.0);
+        let (panel_width, panel_height) = (panel.offset_width() as f64, panel.offset_height() as f64);
+
+        // prefer the right of the marker, flip to the left if there's no room
+        let mut left = rect.right() + PANEL_GAP;
+        if left + panel_width > viewport_width - VIEWPORT_MARGIN {
+            left = rect.left() - PANEL_GAP - panel_width;
+        }
+        let top = (rect.top() + rect.height() / 2.0 - panel_height / 2.0)
+            .min(viewport_height - panel_height - VIEWPORT_MARGIN)
+            .max(VIEWPORT_MARGIN);
+        anchor.set(Some((left.max(VIEWPORT_MARGIN), top)));
+    };
+
+    create_effect(move |_| {
+        selected.track();
+        view_box.track();
+        heading.track();
+        objs.track();
+        // measure once the panel contents and the plot have been rendered
+        request_animation_frame(reposition);
+    });
+
+    let _ = use_event_listener(use_window(), ev::scroll, move |_| reposition());
+    let _ = use_event_listener(use_window(), ev::resize, move |_| reposition());
+
+    let panel_style = move || match (selected.get(), anchor.get()) {
+        (Some(_), Some((left, top))) => format!(
+            "position: fixed; left: {}px; top: {}px; max-height: calc(100vh - {}px); overflow-y: auto;",
+            left,
+            top,
+            2.0 * VIEWPORT_MARGIN
+        ),
+        // keep the panel laid out but invisible so that it can be measured
+        (Some(_), None) => "position: fixed; left: 0; top: 0; visibility: hidden;".to_string(),
+        _ => "display: none;".to_string(),
+    };
+
+    let contents = move || {
+        let id = selected.get()?;
+        let (position, _) = state.position_time.get();
+        objs.with(|objs| {
+            let obj = objs.iter().find(|o| o.id == id)?;
+            let rows = detail_rows(obj, objs, &position)
+                .into_iter()
+                .map(|(label, value)| {
+                    view! {
+                        <dt class="opacity-75">{label}</dt>
+                        <dd class="text-right">{value}</dd>
+                    }
+                })
+                .collect_view();
+            Some(view! {
+                <div class="flex justify-between items-center mb-1">
+                    <h3 class="font-semibold">{obj.name.clone()}</h3>
+                    <button aria-label="Close" on:click=move |_| state.dispatch(Action::Select(None))>"×"</button>
+                </div>
+                <dl class="grid grid-cols-2 gap-x-2 text-sm">{rows}</dl>
+            })
+        })
+    };
+
+    view! {
+        <div
+            node_ref=panel_ref
+            role="dialog"
+            style={panel_style}
+            class="z-10 w-72 text-panel-ink rounded-md bg-panel py-2 px-3 opacity-95 shadow-lg"
+        >
+            {contents}
+        </div>
+    }
+}
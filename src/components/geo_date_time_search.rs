@@ -1,120 +1,234 @@
 use chrono::{DateTime, Utc, Local, TimeZone};
 use leptos::*;
+use leptos_use::{signal_debounced, storage::{use_local_storage, JsonCodec, StringCodec}};
+use web_sys::KeyboardEvent;
 
-use crate::{api::search, errors::AppError, models::{AstronObject, AstronObjectResponse, Position, SearchQueryParams, SearchResponse}, AstronObjectsRw};
+use crate::{api::search, components::TimeSystemsPanel, coords::{format_lat_lon, CoordinateNotation}, errors::AppError, gazetteer::{match_range, use_gazetteer}, models::{AstronObject, Position, SearchItem, SearchQueryParams, SearchResponse, SkyTarget}, state::{use_app_state, Action}, AstronObjectsRw};
 
-async fn geo_search(query: Option<String>) -> Result<Option<SearchResponse>, AppError>
+/// shorter queries match too much to be worth a request
+const MIN_QUERY_LEN: usize = 2;
+const SEARCH_DEBOUNCE_MS: f64 = 300.0;
+const MAX_RECENT_SEARCHES: usize = 5;
+const RECENT_SEARCHES_STORAGE_KEY: &str = "planet-tracker-recent-searches";
+const LISTBOX_ID: &str = "geo-search-listbox";
+const COORDINATE_NOTATION_STORAGE_KEY: &str = "planet-tracker-dms";
+
+fn option_id(i: usize) -> String
+{
+    format!("geo-search-option-{}", i)
+}
+
+fn search_params(query: String) -> SearchQueryParams
+{
+    SearchQueryParams { q: query, max_results: 5, fuzzy: 0.8 }
+}
+
+/// server results along with the query they are for
+async fn geo_search(query: Option<String>) -> Result<Option<(String, SearchResponse)>, AppError>
 {
     if let Some(query) = query {
-        let query = SearchQueryParams { q: query, max_results: 5, fuzzy: 0.8 };
-        search(query).await.map(|r| Some(r))
+        search(search_params(query.clone())).await.map(|r| Some((query, r)))
     } else {
         Ok(None)
     }
 }
 
+/// `name` with the part that matched `query` marked
+fn highlighted(name: &str, query: &str) -> View
+{
+    let Some(range) = match_range(query, name) else {
+        return name.to_string().into_view();
+    };
+    let part = |skip: usize, take: usize| name.chars().skip(skip).take(take).collect::<String>();
+    view! {
+        {part(0, range.start)}
+        <mark class="bg-transparent text-inherit underline">{part(range.start, range.len())}</mark>
+        {part(range.end, usize::MAX)}
+    }.into_view()
+}
+
 
+/// Location search as an ARIA combobox: results as you type, from the
+/// bundled gazetteer at once and from the server once typing pauses, and
+/// recent choices when the input is empty.
 #[component]
 pub fn GeoSearch() -> impl IntoView
 {
-    let (hidden, set_hidden) = create_signal(true);
-    let (query, set_query) = create_signal::<Option<String>>(None);
-    
-    let position_time_rw = use_context::<RwSignal<(Position, DateTime<Utc>)>>().unwrap();
+    let (text, set_text) = create_signal(String::new());
+    let (open, set_open) = create_signal(false);
+    let (active, set_active) = create_signal::<Option<usize>>(None);
+    let (recent, set_recent, _) = use_local_storage::<Vec<SearchItem>, JsonCodec>(RECENT_SEARCHES_STORAGE_KEY);
 
+    let state = use_app_state();
+    let gazetteer = use_gazetteer();
+
+    let trimmed = move || text.get().trim().to_string();
+    let debounced = signal_debounced(text, SEARCH_DEBOUNCE_MS);
+    let query = create_memo(move |_| {
+        let q = debounced.get().trim().to_string();
+        (q.chars().count() >= MIN_QUERY_LEN).then_some(q)
+    });
     let search_results = create_resource(query, geo_search);
 
-    let on_change = move |evt: web_sys::Event| {
-        let value = event_target_value(&evt);
-        if value != "" {
-            set_query.set(Some(value));
-        } else {
-            set_query.set(None);
-            set_hidden.set(true);
+    let showing_recent = move || trimmed().is_empty();
+
+    // the gazetteer answers straight away and when offline; the server's
+    // answer replaces it once it arrives for the same query
+    let items = create_memo(move |_| -> Vec<SearchItem> {
+        let q = trimmed();
+        if q.is_empty() {
+            return recent.get();
         }
+        if q.chars().count() < MIN_QUERY_LEN {
+            return Vec::new();
+        }
+        let from_server = search_results
+            .get()
+            .and_then(|res| res.ok())
+            .flatten()
+            .filter(|(for_query, _)| *for_query == q)
+            .map(|(_, res)| res.items);
+        from_server
+            .or_else(|| gazetteer.search(&search_params(q)).map(|res| res.items))
+            .unwrap_or_default()
+    });
+
+    let expanded = move || open.get() && !items.with(|items| items.is_empty());
+
+    let choose = move |item: SearchItem| {
+        state.dispatch(Action::SetPosition(Position::from(item.clone())));
+        set_recent.update(|recent| {
+            recent.retain(|r| *r != item);
+            recent.insert(0, item.clone());
+            recent.truncate(MAX_RECENT_SEARCHES);
+        });
+        set_text.set(item.name);
+        set_open.set(false);
+        set_active.set(None);
+    };
+
+    let on_input = move |evt: web_sys::Event| {
+        set_text.set(event_target_value(&evt));
+        set_open.set(true);
+        set_active.set(None);
     };
 
-    let success_view = move || {
-        search_results.and_then(|data| {
-            match data {
-                None => {
-                    set_hidden.set(true);
-                    view! { <></> }.into_view()
-                },
-                Some(data) => {
-                    set_hidden.set(false);
-                    let items = data.items
-                        .clone()
-                        .into_iter()
-                        .map(|i| {
-                            let name = i.name.clone();
-                            let paranthetical_text = format!(" ({}, {})", i.country, i.sub_division);
-                            view! {
-                                <div
-                                    class="flex hover:bg-gray-200 w-full py-1 px-2"
-                                    on:click=move |_| { 
-                                        position_time_rw.update(|pt: &mut (Position, DateTime<Utc>)| {
-                                            let new_pos = Position::from(i.clone());
-                                            *pt = (new_pos, pt.1);
-                                        });
-                                        // set_geo_location.set(Some(i.clone()));
-                                        set_hidden.set(true);
-                                    }
-                                >
-                                    <span class="font-medium">{name.clone()}</span><span class="whitespace-pre">{paranthetical_text}</span>
-                                </div>
-                            }
-                        }).collect_view();
-                    items.into_view()
+    let on_keydown = move |evt: KeyboardEvent| {
+        let count = items.with(|items| items.len());
+        match evt.key().as_str() {
+            "ArrowDown" | "ArrowUp" if count > 0 => {
+                evt.prevent_default();
+                set_open.set(true);
+                let step = if evt.key() == "ArrowDown" { 1 } else { count - 1 };
+                set_active.update(|active| {
+                    *active = Some(match *active {
+                        Some(i) => (i + step) % count,
+                        None if step == 1 => 0,
+                        None => count - 1,
+                    })
+                });
+            }
+            "Enter" => {
+                let item = active.get_untracked().and_then(|i| items.with_untracked(|items| items.get(i).cloned()));
+                if let Some(item) = item {
+                    evt.prevent_default();
+                    choose(item);
                 }
             }
-        })
+            "Escape" => {
+                set_open.set(false);
+                set_active.set(None);
+            }
+            _ => {}
+        }
+    };
+
+    let items_view = move || {
+        let q = trimmed();
+        items
+            .get()
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let paranthetical_text = format!(" ({}, {})", item.country, item.sub_division);
+                let is_active = move || active.get() == Some(i);
+                let name = highlighted(&item.name, &q);
+                view! {
+                    <div
+                        id={option_id(i)}
+                        role="option"
+                        aria-selected={move || is_active().to_string()}
+                        class="flex hover:bg-highlight w-full py-1 px-2 cursor-pointer"
+                        class=("bg-highlight", is_active)
+                        // keep focus in the input
+                        on:mousedown=|evt| evt.prevent_default()
+                        on:click=move |_| choose(item.clone())
+                    >
+                        <span class="font-medium">{name}</span><span class="whitespace-pre">{paranthetical_text}</span>
+                    </div>
+                }
+            }).collect_view()
     };
 
     view! {
         <div>
-            <label class="font-bold">"Geolocation Search"</label>
+            <label class="font-bold" for="geo-search">"Geolocation Search"</label>
             <input 
+                id="geo-search"
                 type="search"
+                role="combobox"
+                autocomplete="off"
+                aria-autocomplete="list"
+                aria-controls=LISTBOX_ID
+                aria-expanded={move || expanded().to_string()}
+                aria-activedescendant={move || active.get().map(option_id)}
                 placeholder="Try \"Berlin\" or \"Mumbai\""
-                on:input=on_change
-                class=("search-input-active", move || ! hidden.get())
-                class=("search-input-inactive", move || hidden.get())
-                class="py-1 px-2 border-gray-300 hover:border-gray-500 focus:border-gray-700 focus:outline-none w-full"
+                prop:value=move || text.get()
+                on:input=on_input
+                on:keydown=on_keydown
+                on:focus=move |_| {
+                    gazetteer.load();
+                    set_open.set(true);
+                }
+                on:blur=move |_| set_open.set(false)
+                class=("search-input-active", expanded)
+                class=("search-input-inactive", move || !expanded())
+                class="py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none w-full"
             />
-            <Transition fallback=move || { view! {<div>"Loading..."</div>}}>
-                <ErrorBoundary fallback=move |_| { view! {<div>"Error!"</div>}}>
-                    <div
-                        class:hidden={move || hidden.get()}
-                        class="flex flex-col divide-y divide-solid rounded-b-md border border-solid border-1 border-gray-300"
-                    >
-                        { success_view }
-                    </div>  
-                </ErrorBoundary>
-            </Transition>
+            <div
+                id=LISTBOX_ID
+                role="listbox"
+                aria-label={move || if showing_recent() { "Recent searches" } else { "Search results" }}
+                class:hidden={move || !expanded()}
+                class="flex flex-col divide-y divide-solid rounded-b-md border border-solid border-1 border-line"
+            >
+                <Show when=showing_recent>
+                    <div class="py-1 px-2 text-sm text-muted" role="presentation">"Recent"</div>
+                </Show>
+                { items_view }
+            </div>
         </div>
     }
 }
 
 
 #[component]
-pub fn DateTimeSearch(sun: AstronObjectResponse) -> impl IntoView
+pub fn DateTimeSearch(sun: SkyTarget) -> impl IntoView
 {
-    let position_time_rw = use_context::<RwSignal<(Position, DateTime<Utc>)>>().unwrap();
+    let state = use_app_state();
     let now = Utc::now();
     
     let when = Utc.from_local_datetime(&sun.when).unwrap();
 
-    let setting_time = Utc.from_local_datetime(&sun.setting_time).unwrap();
-    let rising_time = Utc.from_local_datetime(&sun.rising_time).unwrap();
+    // the Sun may not set or rise in the day ahead, near the poles
+    let setting_time = sun.setting_time.map(|t| Utc.from_local_datetime(&t).unwrap());
+    let rising_time = sun.rising_time.map(|t| Utc.from_local_datetime(&t).unwrap());
 
     // logging::log!("when={:?}, now={:?}, setting_time={:?}, rising_time={:?}", when, now, setting_time, rising_time);
 
     let on_click_factory = move |dt: DateTime<Utc>| {
         move |_evt: web_sys::MouseEvent| {
-            position_time_rw.update(|pt| {
-                *pt = (pt.0.clone(), dt)
-            })
+            state.dispatch(Action::SetTime(dt))
         }
     };
 
@@ -127,9 +241,10 @@ pub fn DateTimeSearch(sun: AstronObjectResponse) -> impl IntoView
                 border-solid
                 py-1
                 px-2
-                border-gray-300
-                hover:border-gray-500
-                focus:border-gray-700
+                bg-surface
+                border-line
+                hover:border-line-strong
+                focus:border-ink
                 focus:outline-none
                 w-full"
                 on:click=on_click_factory(now)
@@ -137,21 +252,27 @@ pub fn DateTimeSearch(sun: AstronObjectResponse) -> impl IntoView
         }
     }
 
-    let options =  if rising_time > setting_time {
-        view! {
+    let options = match (setting_time, rising_time) {
+        (Some(setting_time), Some(rising_time)) if rising_time > setting_time => view! {
             <option on:click=on_click_factory(setting_time)>"Today at sunset"</option>
             <option on:click=on_click_factory(rising_time)>"Tomorrow at dawn"</option>
-        }.into_view()
-    } else {
-        view! {
+        }.into_view(),
+        (Some(setting_time), Some(rising_time)) => view! {
             <option on:click=on_click_factory(rising_time)>"Tomorrow at dawn"</option>
             <option on:click=on_click_factory(setting_time)>"Tomorrow at sunset"</option>
-        }.into_view()
+        }.into_view(),
+        (Some(setting_time), None) => view! {
+            <option on:click=on_click_factory(setting_time)>"Today at sunset"</option>
+        }.into_view(),
+        (None, Some(rising_time)) => view! {
+            <option on:click=on_click_factory(rising_time)>"Tomorrow at dawn"</option>
+        }.into_view(),
+        (None, None) => ().into_view(),
     };
 
     view! {
         <label class="font-bold">"Date/time selector"</label>
-        <select class="rounded-md border border-solid py-1 px-2 border-gray-300 hover:border-gray-500 focus:border-gray-700 focus:outline-none w-full">
+        <select class="rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none w-full">
             <option value="now" on:click=on_click_factory(now)>"Now"</option>
             {options}
         </select>
@@ -166,7 +287,7 @@ pub fn GeoDateTimeSearch(objs: AstronObjectsRw) -> impl IntoView
         let sun = objs
             .get()
             .iter()
-            .find(|obj| obj.name == AstronObject::Sun)
+            .find(|obj| obj.is(&AstronObject::Sun))
             .unwrap()
             .clone();
         view! {
@@ -174,15 +295,31 @@ pub fn GeoDateTimeSearch(objs: AstronObjectsRw) -> impl IntoView
         }
     };
 
-    let position_time_rw = use_context::<RwSignal<(Position, DateTime<Utc>)>>().unwrap();
+    let position_time = use_app_state().position_time;
+    let gazetteer = use_gazetteer();
+    // the place name comes from the gazetteer, so fetch it straight away
+    gazetteer.load();
+    let (dms, set_dms, _) = use_local_storage::<bool, StringCodec>(COORDINATE_NOTATION_STORAGE_KEY);
+
     let text_display = move || {
         
-        let (position, time) = position_time_rw.get();
+        let (position, time) = position_time.get();
         let time_local = DateTime::<Local>::from(time); 
+        let notation = if dms.get() { CoordinateNotation::Dms } else { CoordinateNotation::Decimal };
+        let place = gazetteer
+            .reverse(position.lat, position.lon)
+            .map(|place| view! { <span class="font-bold">{place.to_string()}</span>" " });
 
         view! {
             "Showing Ephemerides for "
-            <span class="font-bold">{format!("{:.2}", position.lat)}"°N, "{format!("{:.2}", position.lon)}"°E"</span>
+            {place}
+            <button
+                class="font-bold underline decoration-dotted"
+                title="Switch between decimal degrees and degrees, minutes and seconds"
+                on:click=move |_| set_dms.update(|dms| *dms = !*dms)
+            >
+                {format_lat_lon(position.lat, position.lon, notation)}
+            </button>
             " at "
             <span class="font-bold">{time_local.format("%H:%M:%S").to_string()}</span>
             " on "
@@ -198,6 +335,7 @@ pub fn GeoDateTimeSearch(objs: AstronObjectsRw) -> impl IntoView
             </div>
             <div class="sm:flex-1">
                 {text_display}
+                <TimeSystemsPanel/>
             </div>
         </div>
     }
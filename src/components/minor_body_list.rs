@@ -0,0 +1,76 @@
+use leptos::*;
+
+use crate::minor_bodies::{MinorBodies, MinorBody};
+
+/// Panel for adding comets and asteroids by pasting MPC orbital elements,
+/// one body per line, and removing them again.
+#[component]
+pub fn MinorBodyList(minor_bodies: MinorBodies) -> impl IntoView {
+    let text = create_rw_signal(String::new());
+
+    let names = move || {
+        minor_bodies
+            .elements
+            .get()
+            .iter()
+            .filter_map(|line| MinorBody::parse(line).ok())
+            .map(|body| body.name)
+            .collect::<Vec<_>>()
+    };
+
+    let add = move |_| {
+        minor_bodies.add(&text.get_untracked());
+        if minor_bodies.error.get_untracked().is_none() {
+            text.set(String::new());
+        }
+    };
+
+    view! {
+        <details>
+            <summary class="font-bold cursor-pointer">"Comets and asteroids"</summary>
+            <div class="flex flex-col gap-y-1 my-1">
+                <ul class="flex flex-col gap-y-1">
+                    <For
+                        each=names
+                        key=|name| name.clone()
+                        children=move |name: String| {
+                            let remove_name = name.clone();
+                            view! {
+                                <li class="flex items-center gap-x-2">
+                                    {name}
+                                    <button
+                                        aria-label="Remove"
+                                        class="px-2"
+                                        on:click=move |_| minor_bodies.remove(&remove_name)
+                                    >
+                                        "×"
+                                    </button>
+                                </li>
+                            }
+                        }
+                    />
+                </ul>
+                <label class="text-sm text-muted" for="minor-body-elements">
+                    "Paste orbital elements in MPC format, one body per line, as in CometEls.txt or MPCORB.DAT"
+                </label>
+                <textarea
+                    id="minor-body-elements"
+                    rows="3"
+                    spellcheck="false"
+                    class="font-mono text-xs rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none"
+                    prop:value=move || text.get()
+                    on:input=move |evt| text.set(event_target_value(&evt))
+                />
+                <div>
+                    <button
+                        class="rounded-md border border-solid py-1 px-2 border-line hover:border-line-strong"
+                        on:click=add
+                    >
+                        "Add"
+                    </button>
+                </div>
+                {move || minor_bodies.error.get().map(|e| view! { <div class="text-danger">{e.to_string()}</div> })}
+            </div>
+        </details>
+    }
+}
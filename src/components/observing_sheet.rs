@@ -0,0 +1,234 @@
+use chrono::{Local, NaiveDateTime};
+use leptos::*;
+
+use crate::{
+    almanac::{MoonPhase, Twilight},
+    components::{polar_plot::marker_radius, TextDisplay},
+    gazetteer::use_gazetteer,
+    label_layout::{estimate_text_width, LabelKind, LabelLayout, Marker},
+    models::AstronObject,
+    projection::{ProjectionKind, SkyTransform},
+    state::use_app_state,
+    theme::{use_theme, Theme, ThemeKind},
+    utils::naive_utc_to_local,
+    AstronObjectsRw,
+};
+
+/// plot size in svg units; it is printed at `SHEET_PLOT_WIDTH` whatever the
+/// window size
+const SHEET_PLOT_SIZE: f64 = 400.0;
+const SHEET_PLOT_WIDTH: &str = "95mm";
+const SHEET_PLOT_RADIUS: f64 = 160.0;
+const SHEET_FONT_SIZE: f64 = 11.0;
+const NOTE_LINES: usize = 8;
+
+fn format_time(t: Option<NaiveDateTime>) -> String {
+    t.map_or("-".to_string(), |t| naive_utc_to_local(&t).format("%H:%M").to_string())
+}
+
+/// The sky at the sheet's time in black and white, with every object
+/// above the horizon marked and named.
+#[component]
+fn SheetPlot(objs: AstronObjectsRw) -> impl IntoView {
+    let centre = SHEET_PLOT_SIZE / 2.0;
+    let sky = SkyTransform {
+        projection: ProjectionKind::default(),
+        radius: SHEET_PLOT_RADIUS,
+        center_x: centre,
+        center_y: centre,
+        below_horizon: false,
+    };
+    let elevations = [0.0f64, 15.0, 30.0, 45.0, 60.0, 75.0];
+    let spokes: Vec<f64> = (0..12).map(|i| (i * 30) as f64).collect();
+
+    let visible = move || {
+        objs.get()
+            .into_iter()
+            .filter(|obj| obj.el > 0.0)
+            .collect::<Vec<_>>()
+    };
+
+    let rings = elevations
+        .iter()
+        .map(|el| {
+            view! {
+                <circle cx={centre} cy={centre} r={sky.el_radius(el.to_radians())} stroke="black" stroke-width="0.75" fill="none"/>
+            }
+        })
+        .collect_view();
+    let az_lines = spokes
+        .iter()
+        .map(|az| {
+            let (x, y) = sky.az_r_rel(az.to_radians(), 1.0);
+            view! {
+                <line x1={centre} y1={centre} x2={x} y2={y} stroke="black" stroke-width="0.5" stroke-dasharray="2 3"/>
+            }
+        })
+        .collect_view();
+
+    let labels = move || {
+        let markers: Vec<Marker> = visible()
+            .iter()
+            .map(|obj| {
+                let (x, y) = sky.az_el(obj.az, obj.el);
+                Marker { x, y, r: marker_radius(obj.size, SHEET_PLOT_SIZE) }
+            })
+            .collect();
+        let measure = |text: &str| estimate_text_width(text, SHEET_FONT_SIZE);
+        LabelLayout { sky, font_size: SHEET_FONT_SIZE, measure: &measure }
+            .layout(&elevations[1..], &spokes, &markers)
+            .into_iter()
+            .map(|label| {
+                let weight = if label.kind == LabelKind::Cardinal { "bold" } else { "normal" };
+                view! {
+                    <text
+                        x={label.x}
+                        y={label.y}
+                        text-anchor="middle"
+                        dominant-baseline="central"
+                        font-family="serif"
+                        font-size={SHEET_FONT_SIZE}
+                        font-weight={weight}
+                        fill="black"
+                        stroke="white"
+                        stroke-width="3"
+                        paint-order="stroke"
+                    >
+                        {label.text}
+                    </text>
+                }
+            })
+            .collect_view()
+    };
+
+    let markers = move || {
+        visible()
+            .into_iter()
+            .map(|obj| {
+                let (x, y) = sky.az_el(obj.az, obj.el);
+                let r = marker_radius(obj.size, SHEET_PLOT_SIZE);
+                // the Sun and Moon hollow, so they can be told apart without colour
+                let fill = if obj.is(&AstronObject::Sun) || obj.is(&AstronObject::Moon) {
+                    "white"
+                } else {
+                    "black"
+                };
+                view! {
+                    <circle cx={x} cy={y} r={r} fill={fill} stroke="black" stroke-width="1"/>
+                    <text x={x + r + 3.0} y={y} dominant-baseline="central" font-family="serif" font-size={SHEET_FONT_SIZE} fill="black" stroke="white" stroke-width="3" paint-order="stroke">
+                        {obj.name.clone()}
+                    </text>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <svg
+            viewBox={format!("0 0 {} {}", SHEET_PLOT_SIZE, SHEET_PLOT_SIZE)}
+            style:width=SHEET_PLOT_WIDTH
+            style:height=SHEET_PLOT_WIDTH
+        >
+            {rings}
+            {az_lines}
+            {labels}
+            {markers}
+        </svg>
+    }
+}
+
+/// One page to take into the field: the plot, the full object table,
+/// twilight times, the Moon's phase and room for notes. Only shown when
+/// printing, and laid out at a fixed size that fits both A4 and Letter.
+#[component]
+pub fn ObservingSheet(objs: AstronObjectsRw) -> impl IntoView {
+    let position_time = use_app_state().position_time;
+    // everything on the sheet is drawn in black on white
+    let theme = use_theme();
+    provide_context(Theme {
+        kind: create_memo(|_| ThemeKind::Print),
+        ..theme
+    });
+    let gazetteer = use_gazetteer();
+
+    let header = move || {
+        let (position, time) = position_time.get();
+        format!(
+            "{} · {}",
+            gazetteer.describe(position.lat, position.lon),
+            time.with_timezone(&Local).format("%A %e %B %Y, %H:%M")
+        )
+    };
+
+    let twilight = create_memo(move |_| {
+        let (position, time) = position_time.get();
+        Twilight::for_night(&position, time.naive_utc())
+    });
+
+    let twilight_rows = move || {
+        let t = twilight.get();
+        [
+            ("Sunset", t.sunset),
+            ("Civil dusk", t.civil_dusk),
+            ("Nautical dusk", t.nautical_dusk),
+            ("Astronomical dusk", t.astronomical_dusk),
+            ("Astronomical dawn", t.astronomical_dawn),
+            ("Nautical dawn", t.nautical_dawn),
+            ("Civil dawn", t.civil_dawn),
+            ("Sunrise", t.sunrise),
+        ]
+        .into_iter()
+        .map(|(label, time)| {
+            view! {
+                <tr>
+                    <td class="pr-4">{label}</td>
+                    <td>{format_time(time)}</td>
+                </tr>
+            }
+        })
+        .collect_view()
+    };
+
+    let moon_phase = move || {
+        objs.with(|objs| {
+            let find = |body: AstronObject| objs.iter().find(|obj| obj.is(&body));
+            let phase = MoonPhase::new(find(AstronObject::Sun)?, find(AstronObject::Moon)?);
+            Some(format!(
+                "{}, {:.0}% lit",
+                phase.name(),
+                100.0 * phase.illuminated
+            ))
+        })
+    };
+
+    let note_lines = (0..NOTE_LINES)
+        .map(|_| view! { <div class="h-[8mm] border-b border-solid"></div> })
+        .collect_view();
+
+    view! {
+        <div class="hidden print:block w-[180mm] mx-auto text-sm">
+            <h1 class="text-2xl font-bold">"Observing sheet"</h1>
+            <div class="mb-2">{header}</div>
+            <div class="flex gap-x-6">
+                <SheetPlot objs=objs/>
+                <div class="flex flex-col gap-y-2">
+                    <div>
+                        <h2 class="font-bold">"Twilight"</h2>
+                        <table>
+                            <tbody>{twilight_rows}</tbody>
+                        </table>
+                    </div>
+                    <div>
+                        <h2 class="font-bold">"Moon"</h2>
+                        <div>{move || moon_phase().unwrap_or("-".to_string())}</div>
+                    </div>
+                </div>
+            </div>
+            <div class="my-2">
+                <TextDisplay objs=objs/>
+            </div>
+            <h2 class="font-bold">"Notes"</h2>
+            {note_lines}
+        </div>
+    }
+}
@@ -0,0 +1,237 @@
+use leptos::*;
+
+use crate::{
+    app::latest_target,
+    components::polar_plot::marker_radius,
+    models::{CardinalDirection, SkyTarget},
+    state::{use_app_state, Action},
+    theme::use_theme,
+    utils::rad2deg,
+    AstronObjectsRw,
+};
+
+pub const FIELDS_OF_VIEW: [f64; 4] = [60.0, 90.0, 180.0, 360.0];
+/// how far below the horizon the panorama reaches, in degrees
+const BELOW_HORIZON: f64 = 5.0;
+const LABEL_HEIGHT: f64 = 20.0;
+
+/// Maps azimuth and elevation in degrees onto a panorama centred on `facing`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanoramaTransform {
+    pub facing: f64,
+    pub fov: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PanoramaTransform {
+    /// highest elevation shown; keeps degrees roughly square on screen
+    pub fn el_max(&self) -> f64 {
+        (self.fov * self.height / self.width).min(90.0)
+    }
+
+    /// azimuth relative to `facing`, in [-180, 180)
+    fn relative_az(&self, az: f64) -> f64 {
+        (az - self.facing + 540.0).rem_euclid(360.0) - 180.0
+    }
+
+    pub fn x(&self, az: f64) -> Option<f64> {
+        let rel = self.relative_az(az);
+        (rel.abs() <= self.fov / 2.0).then(|| self.width / 2.0 + rel * self.width / self.fov)
+    }
+
+    pub fn y(&self, el: f64) -> f64 {
+        let span = self.el_max() + BELOW_HORIZON;
+        self.height * (self.el_max() - el) / span
+    }
+}
+
+#[component]
+pub fn PanoramaObjectView(
+    obj: Memo<SkyTarget>,
+    transform: Memo<PanoramaTransform>,
+    width: f64,
+) -> impl IntoView {
+    let theme = use_theme();
+    let state = use_app_state();
+    let obj_size = move || marker_radius(obj.get().size, width);
+
+    move || {
+        let obj = obj.get();
+        let t = transform.get();
+        let (az, el) = (rad2deg(obj.az), rad2deg(obj.el));
+        let x = t.x(az)?;
+        if el < -BELOW_HORIZON {
+            return None;
+        }
+        let y = t.y(el);
+        let id = obj.id.clone();
+        let palette = theme.palette();
+        let (fill, opacity) = if obj.el > 0.0 {
+            (obj.get_color(theme.kind.get()), "1.0")
+        } else {
+            (palette.below_horizon.to_string(), "0.4")
+        };
+        let is_selected = {
+            let id = id.clone();
+            move || state.selected.get() == Some(id.clone())
+        };
+        Some(view! {
+            <g style="cursor: pointer" on:click=move |_| state.dispatch(Action::ToggleSelected(id.clone()))>
+                <circle
+                    cx={x}
+                    cy={y}
+                    r={move || obj_size() + 3.0}
+                    fill="none"
+                    stroke={palette.ink}
+                    stroke-width="2"
+                    visibility={move || if is_selected() { "visible" } else { "hidden" }}
+                />
+                <circle cx={x} cy={y} r={obj_size} fill={fill} opacity={opacity}/>
+                <text x={x} y={move || y - obj_size() - 4.0} text-anchor="middle" font-family="serif" font-size="10" fill={palette.ink}>
+                    {obj.name.clone()}
+                </text>
+            </g>
+        })
+    }
+}
+
+/// Horizon view: azimuth along x, elevation up y, for the field of view
+/// centred on the `facing` azimuth (degrees).
+#[component]
+pub fn PanoramaPlot(
+    width: usize,
+    objs: AstronObjectsRw,
+    facing: RwSignal<f64>,
+    fov: RwSignal<f64>,
+) -> impl IntoView {
+    let theme = use_theme();
+    let plot_width = width as f64;
+    let plot_height = (plot_width / 2.0).round();
+
+    let transform = create_memo(move |_| PanoramaTransform {
+        facing: facing.get(),
+        fov: fov.get(),
+        width: plot_width,
+        height: plot_height,
+    });
+
+    let ground = move || {
+        let y = transform.get().y(0.0);
+        let palette = theme.palette();
+        view! {
+            <rect x=0 y={y} width={plot_width} height={plot_height - y} fill={palette.ground}/>
+            <line x1=0 x2={plot_width} y1={y} y2={y} stroke={palette.ink} stroke-width="1"/>
+        }
+    };
+
+    let el_lines = move || {
+        let t = transform.get();
+        let palette = theme.palette();
+        let step = if t.el_max() > 45.0 { 15 } else { 10 };
+        (1..=(t.el_max() as usize / step))
+            .map(|i| {
+                let el = (i * step) as f64;
+                let y = t.y(el);
+                view! {
+                    <line x1=0 x2={plot_width} y1={y} y2={y} stroke={palette.ink} stroke-width="0.5" stroke-dasharray="2 4"/>
+                    <text x=2 y={y - 2.0} font-family="serif" font-size="10" fill={palette.ink}>{format!("{:.0}°", el)}</text>
+                }
+            })
+            .collect_view()
+    };
+
+    let az_lines = move || {
+        let t = transform.get();
+        let palette = theme.palette();
+        let step = if t.fov > 90.0 { 30 } else { 15 };
+        (0..360 / step)
+            .filter_map(|i| {
+                let az = (i * step) as f64;
+                let x = t.x(az)?;
+                Some(view! {
+                    <line x1={x} x2={x} y1=0 y2={t.y(0.0)} stroke={palette.ink} stroke-width="0.5" stroke-dasharray="2 4"/>
+                })
+            })
+            .collect_view()
+    };
+
+    let cardinal_labels = move || {
+        let t = transform.get();
+        let palette = theme.palette();
+        (0..8u8)
+            .filter_map(|i| {
+                let x = t.x(45.0 * i as f64)?;
+                let label = format!("{:#}", CardinalDirection::from(i + 1));
+                Some(view! {
+                    <text x={x} y={plot_height + LABEL_HEIGHT - 6.0} text-anchor="middle" font-family="serif" font-size="12" font-weight="bold" fill={palette.ink}>
+                        {label}
+                    </text>
+                })
+            })
+            .collect_view()
+    };
+
+    let fov_options = FIELDS_OF_VIEW
+        .iter()
+        .map(|value| {
+            let value = *value;
+            view! {
+                <option value={value} selected={move || fov.get() == value}>{format!("{:.0}°", value)}</option>
+            }
+        })
+        .collect_view();
+
+    let turn = move |delta: f64| facing.update(|f| *f = (*f + delta).rem_euclid(360.0));
+
+    view! {
+        <div class="content-center justify-center">
+            <div class="flex flex-wrap items-center gap-x-4 gap-y-1 my-1">
+                <label class="flex items-center gap-x-2">
+                    <span class="font-bold">"Field of view"</span>
+                    <select
+                        class="rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none"
+                        on:change=move |evt| {
+                            if let Ok(value) = event_target_value(&evt).parse::<f64>() {
+                                fov.set(value);
+                            }
+                        }
+                    >
+                        {fov_options}
+                    </select>
+                </label>
+                <div class="flex items-center gap-x-2">
+                    <span class="font-bold">"Facing"</span>
+                    <button class="rounded-md border border-solid px-2 border-line hover:border-line-strong" on:click=move |_| turn(-45.0)>"◀"</button>
+                    <input
+                        type="range"
+                        min="0"
+                        max="359"
+                        prop:value={move || facing.get().round()}
+                        on:input=move |evt| {
+                            if let Ok(value) = event_target_value(&evt).parse::<f64>() {
+                                facing.set(value);
+                            }
+                        }
+                    />
+                    <button class="rounded-md border border-solid px-2 border-line hover:border-line-strong" on:click=move |_| turn(45.0)>"▶"</button>
+                </div>
+            </div>
+            <svg width={width} height={plot_height + LABEL_HEIGHT} style="display: block; margin: auto;">
+                {ground}
+                {el_lines}
+                {az_lines}
+                {cardinal_labels}
+                <For
+                    each=move || objs.get()
+                    key=|obj| obj.id.clone()
+                    children=move |obj: SkyTarget| {
+                        view! {
+                            <PanoramaObjectView obj=latest_target(objs, obj) transform=transform width=plot_width/>
+                        }
+                    }
+                />
+            </svg>
+        </div>
+    }
+}
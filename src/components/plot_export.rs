@@ -0,0 +1,124 @@
+use chrono::Local;
+use leptos::{svg::Svg, *};
+
+use crate::{
+    errors::AppError,
+    export::{download, download_svg, standalone_svg, svg_to_png, Caption},
+    gazetteer::use_gazetteer,
+    state::use_app_state,
+    theme::use_theme,
+    AstronObjectsRw,
+};
+
+/// PNG sizes offered, as multiples of the on-screen plot size
+const PNG_SCALES: [f64; 3] = [1.0, 2.0, 4.0];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Svg,
+    Png(f64),
+}
+
+/// Download buttons for the polar plot `plot`, of `width` by `height`
+/// pixels, as an SVG or a PNG with a caption and legend underneath.
+#[component]
+pub fn PlotExport(
+    plot: NodeRef<Svg>,
+    objs: AstronObjectsRw,
+    width: usize,
+    height: usize,
+) -> impl IntoView {
+    let theme = use_theme();
+    let gazetteer = use_gazetteer();
+    let position_time = use_app_state().position_time;
+    let scale = create_rw_signal(2.0);
+    let error = create_rw_signal::<Option<AppError>>(None);
+
+    let caption = move || {
+        let (position, time) = position_time.get_untracked();
+        let kind = theme.kind.get_untracked();
+        Caption {
+            location: gazetteer.describe(position.lat, position.lon),
+            time: time
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string(),
+            legend: objs
+                .get_untracked()
+                .iter()
+                .map(|obj| (obj.name.clone(), obj.get_color(kind)))
+                .collect(),
+        }
+    };
+
+    let export = move |format: ExportFormat| {
+        let Some(svg) = plot.get_untracked() else {
+            return;
+        };
+        let (plot_width, plot_height) = (width as f64, height as f64);
+        let filename = format!(
+            "sky-{}",
+            position_time
+                .get_untracked()
+                .1
+                .with_timezone(&Local)
+                .format("%Y%m%d-%H%M")
+        );
+        let result = standalone_svg(
+            &svg,
+            plot_width,
+            plot_height,
+            &caption(),
+            theme.palette(),
+        );
+        let (markup, total_height) = match result {
+            Ok(result) => result,
+            Err(e) => return error.set(Some(e)),
+        };
+        match format {
+            ExportFormat::Svg => {
+                error.set(download_svg(&markup, &format!("{}.svg", filename)).err());
+            }
+            ExportFormat::Png(scale) => spawn_local(async move {
+                let result = svg_to_png(&markup, plot_width, total_height, scale)
+                    .await
+                    .and_then(|url| download(&url, &format!("{}.png", filename)));
+                error.set(result.err());
+            }),
+        }
+    };
+
+    let scale_options = PNG_SCALES
+        .iter()
+        .map(|value| {
+            let value = *value;
+            view! {
+                <option value={value} selected={move || scale.get() == value}>
+                    {format!("{:.0} × {:.0}", value * width as f64, value * height as f64)}
+                </option>
+            }
+        })
+        .collect_view();
+
+    let button_class = "rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong";
+
+    view! {
+        <div class="flex flex-wrap items-center gap-x-2 gap-y-1">
+            <span class="font-bold">"Download"</span>
+            <button class={button_class} on:click=move |_| export(ExportFormat::Svg)>"SVG"</button>
+            <button class={button_class} on:click=move |_| export(ExportFormat::Png(scale.get_untracked()))>"PNG"</button>
+            <select
+                class="rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none"
+                aria-label="PNG size"
+                on:change=move |evt| {
+                    if let Ok(value) = event_target_value(&evt).parse::<f64>() {
+                        scale.set(value);
+                    }
+                }
+            >
+                {scale_options}
+            </select>
+            {move || error.get().map(|e| view! { <span class="text-danger">{e.to_string()}</span> })}
+        </div>
+    }
+}
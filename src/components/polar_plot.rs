@@ -1,21 +1,42 @@
-use std::f64::consts::FRAC_PI_2;
+use std::{collections::HashMap, f64::consts::PI};
 
-use leptos::*;
+use enum_iterator::all;
+use leptos::{svg::Svg, *};
 use logging::log;
 use wasm_bindgen::JsCast;
-use web_sys::MouseEvent;
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, KeyboardEvent, MouseEvent, PointerEvent,
+    WheelEvent,
+};
 
 use crate::{
-    app::MIN_POLAR_PLOT_WIDTH,
-    models::{AstronObject, AstronObjectResponse},
+    app::{latest_target, MIN_POLAR_PLOT_WIDTH},
+    clock::use_clock,
+    components::{DetailPanel, PlotExport},
+    coords::format_hms,
+    label_layout::{estimate_text_width, LabelKind, LabelLayout, Marker},
+    models::{SkyTarget, TargetId},
+    projection::{ProjectionKind, SkyTransform},
+    satellites::{use_satellites, SatellitePass},
+    state::{use_app_state, Action, Layer},
+    theme::use_theme,
+    utils::{deg2cardinal, format_crossing, naive_utc_to_local, rad2deg},
     AstronObjectsRw, SelectedRw,
 };
 
+pub const MAX_ZOOM: f64 = 8.0;
+const LABEL_FONT_SIZE: f64 = 10.0;
+const WHEEL_ZOOM_STEP: f64 = 1.15;
+/// points along the meridian, from the north horizon to the south
+const MERIDIAN_STEPS: usize = 36;
+/// where the sidereal time is written along the meridian, in degrees
+const MERIDIAN_LABEL_ELEVATION: f64 = 75.0;
+
 #[derive(Debug, Clone)]
 pub struct Tooltip {
     x: f64,
     y: f64,
-    obj: Option<AstronObject>,
+    obj: Option<TargetId>,
 }
 
 impl Default for Tooltip {
@@ -30,43 +51,109 @@ impl Default for Tooltip {
 
 type TooltipRw = RwSignal<Tooltip>;
 
-fn transform_radius(radius: f64) -> f64 {
-    radius.sqrt()
+/// The visible window of the plot in svg user units. `zoom` of 1.0 shows
+/// the whole plot; `x` and `y` are the top left corner of the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewBox {
+    x: f64,
+    y: f64,
+    zoom: f64,
+}
+
+impl Default for ViewBox {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl ViewBox {
+    /// zoom by `factor`, keeping the point at fraction (`fx`, `fy`) of the
+    /// plot fixed on screen.
+    pub fn zoom_at(&self, factor: f64, fx: f64, fy: f64, width: f64, height: f64) -> Self {
+        let zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        let (px, py) = (
+            self.x + fx * width / self.zoom,
+            self.y + fy * height / self.zoom,
+        );
+        Self {
+            x: px - fx * width / zoom,
+            y: py - fy * height / zoom,
+            zoom,
+        }
+        .clamp(width, height)
+    }
+
+    /// pan by (`dx`, `dy`) screen pixels
+    pub fn pan(&self, dx: f64, dy: f64, width: f64, height: f64) -> Self {
+        Self {
+            x: self.x - dx / self.zoom,
+            y: self.y - dy / self.zoom,
+            zoom: self.zoom,
+        }
+        .clamp(width, height)
+    }
+
+    fn clamp(self, width: f64, height: f64) -> Self {
+        Self {
+            x: self.x.clamp(0.0, width - width / self.zoom),
+            y: self.y.clamp(0.0, height - height / self.zoom),
+            zoom: self.zoom,
+        }
+    }
+
+    pub fn to_attr(self, width: f64, height: f64) -> String {
+        format!(
+            "{} {} {} {}",
+            self.x,
+            self.y,
+            width / self.zoom,
+            height / self.zoom
+        )
+    }
 }
 
-fn transform_az_el(az: f64, el: f64, radius: f64, center_x: f64, center_y: f64) -> (f64, f64) {
-    let el_abs = el.abs();
-    let rad_rel = transform_radius(1.0 - (el_abs / FRAC_PI_2));
-    let cx = radius * rad_rel * (az - FRAC_PI_2).cos();
-    let cy = radius * rad_rel * (az - FRAC_PI_2).sin();
-    (cx + center_x, cy + center_y)
+pub type ViewBoxRw = RwSignal<ViewBox>;
+
+/// screen radius of the marker for an object of apparent `size`
+pub fn marker_radius(size: f64, width: f64) -> f64 {
+    let scale_factor = 1.5 * width / MIN_POLAR_PLOT_WIDTH as f64;
+    2.0 + scale_factor * (size + 1.0).ln()
 }
 
-fn transform_az_r_rel(
-    az: f64,     // azimuth angle
-    r_rel: f64,  // reletive radius
-    radius: f64, // screen radius
-    center_x: f64,
-    center_y: f64,
-) -> (f64, f64) {
-    let cx = radius * r_rel * (az - FRAC_PI_2).cos();
-    let cy = radius * r_rel * (az - FRAC_PI_2).sin();
-    (cx + center_x, cy + center_y)
+/// an offscreen canvas context for measuring label text
+fn text_measure_context() -> Option<CanvasRenderingContext2d> {
+    let canvas = document()
+        .create_element("canvas")
+        .ok()?
+        .dyn_into::<HtmlCanvasElement>()
+        .ok()?;
+    let context = canvas
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<CanvasRenderingContext2d>()
+        .ok()?;
+    context.set_font(&format!("{}px serif", LABEL_FONT_SIZE));
+    Some(context)
 }
 
 #[component]
 pub fn AstronObjectView(
-    radius: f64,
+    sky: Memo<SkyTransform>,
     width: f64,
-    center_x: f64,
-    center_y: f64,
-    obj: AstronObjectResponse,
-    selected: SelectedRw,
+    obj: Memo<SkyTarget>,
+    hovered: SelectedRw,
+    focused: SelectedRw,
     tooltip: TooltipRw,
+    view_box: ViewBoxRw,
+    heading: Signal<Option<f64>>,
 ) -> impl IntoView {
-    let (obj, _) = create_signal(obj);
-    let scale_factor = 1.5 * width as f64 / MIN_POLAR_PLOT_WIDTH as f64;
-    let obj_size = move || 2.0 + scale_factor * (obj.get().size + 1.0).ln();
+    let theme = use_theme();
+    let state = use_app_state();
+    let obj_size = move || marker_radius(obj.get().size, width);
 
     let node_ref = create_node_ref::<leptos::svg::Circle>();
 
@@ -81,76 +168,162 @@ pub fn AstronObjectView(
         (scroll_x as f64, scroll_y as f64)
     };
 
-    create_effect(move |_| {
-        if let (Some(circle), Some(current_selected)) = (node_ref.get(), selected.get()) {
-            let obj = obj.get();
-            if current_selected != obj.name {
-                return;
-            }
-            let rect = circle.get_bounding_client_rect();
-            let (scroll_x, scroll_y) = get_scroll();
-            tooltip.set(Tooltip {
-                x: rect.x() + obj_size() + scroll_x as f64,
-                y: rect.y() + scroll_y as f64,
-                obj: Some(obj.name.clone()),
-            });
+    let update_tooltip = move || {
+        let Some(circle) = node_ref.get_untracked() else {
+            return;
         };
-    });
-
-    let on_click = move |_| {
-        let obj = obj.get();
-        let circle = node_ref.get().expect("circle exists").clone();
         let rect = circle.get_bounding_client_rect();
         let (scroll_x, scroll_y) = get_scroll();
-
-        logging::log!(
-            "rect.x={}, rect.y={}, scroll_x={}, scroll_y={}",
-            rect.x(),
-            rect.y(),
-            scroll_x,
-            scroll_y
-        );
         tooltip.set(Tooltip {
-            x: rect.x() + obj_size() + scroll_x as f64,
-            y: rect.y() + scroll_y as f64,
-            obj: Some(obj.name.clone()),
+            x: rect.x() + rect.width() / 2.0 + scroll_x,
+            y: rect.y() + scroll_y,
+            obj: Some(obj.get_untracked().id),
         });
+    };
 
-        if let Some(current_selected) = selected.get() {
-            if current_selected != obj.name {
-                selected.set(Some(obj.name.clone()));
-            } else {
-                selected.set(None);
-            }
-        } else {
-            selected.set(Some(obj.name.clone()));
+    // hovering wins over keyboard focus; the selection gets a DetailPanel instead
+    create_effect(move |_| {
+        view_box.track();
+        heading.track();
+        let target = hovered.get().or_else(|| focused.get());
+        if node_ref.get().is_some() && target == Some(obj.get().id) {
+            // wait for the view box change to reach the DOM before measuring
+            request_animation_frame(update_tooltip);
+        }
+    });
+
+    let on_click = move |_: MouseEvent| {
+        state.dispatch(Action::ToggleSelected(obj.get().id));
+    };
+
+    let on_mouseenter = move |_: MouseEvent| {
+        hovered.set(Some(obj.get().id));
+    };
+
+    let on_mouseleave = move |_: MouseEvent| {
+        if hovered.get_untracked() == Some(obj.get().id) {
+            hovered.set(None);
         }
     };
 
-    let circle_view = move || {
+    move || {
         let obj = obj.get();
-        let (cx, cy) = transform_az_el(obj.az, obj.el, radius, center_x, center_y);
-        let astron_obj = obj.name.clone();
+        let (cx, cy) = sky.get().az_el(obj.az, obj.el);
+        let id = obj.id.clone();
 
         let (fill, opacity) = if obj.el > 0.0 {
-            (astron_obj.get_color(), "1.0")
+            (obj.get_color(theme.kind.get()), "1.0")
         } else {
-            ("rgba(180, 180, 180)", "0.4")
+            (theme.palette().below_horizon.to_string(), "0.4")
+        };
+
+        let focus_ring = move || {
+            (focused.get() == Some(id.clone())).then(|| {
+                view! {
+                    <circle
+                        cx={cx}
+                        cy={cy}
+                        r={obj_size() + 3.0}
+                        fill="none"
+                        stroke={theme.palette().accent}
+                        stroke-width="2"
+                        pointer-events="none"
+                    />
+                }
+            })
         };
 
         view! {
+            {focus_ring}
             <circle
                 node_ref={node_ref}
+                data-object={obj.id.0.clone()}
                 cx={cx}
                 cy={cy}
                 fill={fill}
                 opacity={opacity}
                 r={obj_size}
+                style="cursor: pointer"
                 on:click=on_click
+                on:mouseenter=on_mouseenter
+                on:mouseleave=on_mouseleave
             />
         }
+    }
+}
+
+/// A satellite pass as an arc across the sky, solid where the satellite is
+/// sunlit and dashed in the Earth's shadow, with a dot where it is highest.
+/// Clicking it selects the pass.
+#[component]
+pub fn SatellitePassView(sky: Memo<SkyTransform>, pass: SatellitePass) -> impl IntoView {
+    let theme = use_theme();
+    let satellites = use_satellites();
+    let id = store_value(pass.id());
+    let is_selected = move || id.with_value(|id| satellites.selected.get().as_ref() == Some(id));
+    let on_click = move |_: MouseEvent| {
+        let id = id.get_value();
+        satellites.selected.update(|selected| {
+            *selected = if selected.as_ref() == Some(&id) { None } else { Some(id) };
+        })
+    };
+
+    let title = format!(
+        "{} {}–{}, highest {:.0}°",
+        pass.satellite,
+        naive_utc_to_local(&pass.start.when).format("%H:%M"),
+        naive_utc_to_local(&pass.end.when).format("%H:%M"),
+        rad2deg(pass.max.el)
+    );
+
+    let arc_view = move || {
+        let sky = sky.get();
+        let palette = theme.palette();
+        let stroke = if is_selected() { palette.accent } else { palette.muted };
+        let stroke_width = if is_selected() { "3" } else { "1.5" };
+        let mut points = vec![pass.start];
+        points.extend(pass.track.iter().copied());
+        points.push(pass.end);
+        // one polyline per run of sunlit or shadowed points, sharing their
+        // end points so the arc is unbroken
+        let segments = points
+            .windows(2)
+            .fold(Vec::<(bool, Vec<(f64, f64)>)>::new(), |mut segments, pair| {
+                let sunlit = pair[1].sunlit;
+                let (x, y) = sky.az_el(pair[1].az, pair[1].el);
+                match segments.last_mut() {
+                    Some((last, xy)) if *last == sunlit => xy.push((x, y)),
+                    _ => segments.push((sunlit, vec![sky.az_el(pair[0].az, pair[0].el), (x, y)])),
+                }
+                segments
+            });
+        let (max_x, max_y) = sky.az_el(pass.max.az, pass.max.el);
+        view! {
+            {segments
+                .into_iter()
+                .map(|(sunlit, xy)| {
+                    let points = xy.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" ");
+                    view! {
+                        <polyline
+                            points={points}
+                            fill="none"
+                            stroke={stroke}
+                            stroke-width={stroke_width}
+                            stroke-dasharray={if sunlit { "none" } else { "4 3" }}
+                        />
+                    }
+                })
+                .collect_view()}
+            <circle cx={max_x} cy={max_y} r="3" fill={stroke}/>
+        }
     };
-    view! { {circle_view} }
+
+    view! {
+        <g style="cursor: pointer" on:click=on_click>
+            <title>{title}</title>
+            {arc_view}
+        </g>
+    }
 }
 
 #[component]
@@ -159,7 +332,9 @@ pub fn PolarPlot(
     height: usize,
     radius: usize,
     objs: AstronObjectsRw,
-    selected: SelectedRw,
+    projection: RwSignal<ProjectionKind>,
+    /// compass heading to put at the top of the plot, in degrees
+    heading: Signal<Option<f64>>,
 ) -> impl IntoView {
     log!(
         "PolarPlot: width={}, height={}, radius={}",
@@ -168,6 +343,11 @@ pub fn PolarPlot(
         radius
     );
 
+    let theme = use_theme();
+    let satellites = use_satellites();
+    let clock = use_clock();
+    let state = use_app_state();
+
     let padding = width / 2 - radius;
     log!("PolarPlot: padding={}", padding);
 
@@ -181,62 +361,302 @@ pub fn PolarPlot(
         .collect();
     let (center_x, center_y) = (radius + padding, radius + padding / 2);
 
-    let el_circles = el_lines
-        .iter()
-        .map(|r_line| {
-            let rad_norm = 1.0 - (r_line / 90.0);
-            let rad_transformed = transform_radius(rad_norm);
-            let r = radius as f64 * rad_transformed;
-            let (x, y) = if *r_line == 0.0 {
-                transform_az_r_rel(11.0_f64.to_radians(), 1.01, radius as f64, center_x as f64, center_y as f64)
-            } else {
-                transform_az_el(10.0_f64.to_radians(), (r_line - 1.0).to_radians(), radius as f64, center_x as f64, center_y as f64)
-            };
-            let text = format!("{:.0}°", r_line);
-            let transform = format!("rotate(10 {} {})", x, y);
+    let sky = create_memo(move |_| SkyTransform {
+        projection: projection.get(),
+        radius: radius as f64,
+        center_x: center_x as f64,
+        center_y: center_y as f64,
+        below_horizon: state.layers.get().shows(Layer::BelowHorizon),
+    });
+
+    let below_horizon_ring = move || {
+        let sky = sky.get();
+        sky.below_horizon.then(|| {
+            let horizon = sky.horizon_radius();
+            view! {
+                <circle
+                    cx={center_x}
+                    cy={center_y}
+                    r={(horizon + sky.radius) / 2.0}
+                    stroke={theme.palette().ground}
+                    stroke-width={sky.radius - horizon}
+                    fill="none"
+                />
+            }
+        })
+    };
+
+    // the right ascension on the meridian is the local sidereal time
+    let meridian = move || {
+        state.layers.get().shows(Layer::Meridian).then(|| {
+            let sky = sky.get();
+            let palette = theme.palette();
+            let points = (0..=MERIDIAN_STEPS)
+                .map(|i| {
+                    let angle = PI * i as f64 / MERIDIAN_STEPS as f64;
+                    let (x, y) = if angle <= PI / 2.0 {
+                        sky.az_el(0.0, angle)
+                    } else {
+                        sky.az_el(PI, PI - angle)
+                    };
+                    format!("{},{}", x, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let (x, y) = sky.az_el(PI, MERIDIAN_LABEL_ELEVATION.to_radians());
+            let upright = heading.get().unwrap_or(0.0);
             view! {
-                <circle cx={center_x} cy={center_y} r={r} stroke="#1f2937" stroke-width="1" fill="none"/>
-                <text x={x} y={y} font-family="serif" font-size="10" fill="#1f2937" transform={transform}>{text}</text>
+                <polyline
+                    points={points}
+                    fill="none"
+                    stroke={palette.accent}
+                    stroke-width="1"
+                    stroke-dasharray="4 3"
+                    pointer-events="none"
+                />
+                <text
+                    x={x + 4.0}
+                    y={y}
+                    dominant-baseline="central"
+                    font-family="serif"
+                    font-size={LABEL_FONT_SIZE}
+                    fill={palette.accent}
+                    stroke={palette.surface}
+                    stroke-width="3"
+                    paint-order="stroke"
+                    pointer-events="none"
+                    transform={format!("rotate({} {} {})", upright, x, y)}
+                >
+                    {move || format!("LST {}", format_hms(clock.time_systems.get().lst))}
+                </text>
             }
         })
-        .collect::<Vec<_>>();
+    };
+
+    let (ring_elevations, spoke_azimuths) = (el_lines.clone(), az_lines.clone());
 
-    let az_lines = az_lines
+    let el_circles = move || {
+        let sky = sky.get();
+        el_lines
+            .iter()
+            .map(|r_line| {
+                let r = sky.el_radius(r_line.to_radians());
+                view! {
+                    <circle cx={center_x} cy={center_y} r={r} stroke={theme.palette().ink} stroke-width="1" fill="none"/>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let az_spokes = az_lines
         .iter()
         .map(|az_line| {
-            let transform = format!("rotate({} {} {}) translate({}, {})", az_line, center_x, center_y, center_x, center_y);
-            let text = format!("{:.0}°", az_line);
-            let (text_transform, (x, y)) = if *az_line <= 180.0 {
-                let (x, y) = transform_az_r_rel((az_line + 1.0).to_radians(), 1.01, radius as f64, center_x as f64, center_y as f64);
-                (format!("rotate({} {} {})", az_line - 90.0, x, y), (x, y))
-            } else {
-                let transform = |width: f64| -> f64
-                {
-                    let (x0, y0) = (360.0, 1.14);
-                    let (x1, y1) = (768.0, 1.07);
-                    let x_diff = x1 - x0;
-                    let y_diff = y1 - y0;
-                    let m = y_diff / x_diff;
-                    let b = (x1*y0 - x0*y1) / x_diff;
-                    width * m + b
-                };
-                let (x, y) = transform_az_r_rel((az_line - 1.0).to_radians(), transform(width as f64), radius as f64, center_x as f64, center_y as f64);
-                (format!("rotate({} {} {})", az_line - 270.0, x, y), (x, y))
-            };
+            let transform = format!("rotate({} {} {}) translate({}, {})", az_line - 90.0, center_x, center_y, center_x, center_y);
             view! {
-                <line x1=0 x2={radius} y1=0 y2=0 stroke="#1f2937" stroke-width="1" transform={transform}/>
-                <text x={x} y={y} font-family="serif" font-size="10" fill="#1f2937" transform={text_transform}>{text}</text>
+                <line x1=0 x2={radius} y1=0 y2=0 stroke={move || theme.palette().ink} stroke-width="1" transform={transform}/>
             }
         })
         .collect::<Vec<_>>();
 
+    let measure_context = store_value(text_measure_context());
+    let labels = create_memo(move |_| {
+        let sky = sky.get();
+        let markers = objs.with(|objs| {
+            objs.iter()
+                .map(|obj| {
+                    let (x, y) = sky.az_el(obj.az, obj.el);
+                    Marker { x, y, r: marker_radius(obj.size, width as f64) }
+                })
+                .collect::<Vec<_>>()
+        });
+        measure_context.with_value(|context| {
+            let measure = |text: &str| match context {
+                Some(context) => context
+                    .measure_text(text)
+                    .map(|m| m.width())
+                    .unwrap_or_else(|_| estimate_text_width(text, LABEL_FONT_SIZE)),
+                None => estimate_text_width(text, LABEL_FONT_SIZE),
+            };
+            LabelLayout { sky, font_size: LABEL_FONT_SIZE, measure: &measure }.layout(&ring_elevations, &spoke_azimuths, &markers)
+        })
+    });
+
+    let label_views = move || {
+        let palette = theme.palette();
+        // keep the text upright when the plot is turned to the compass heading
+        let upright = heading.get().unwrap_or(0.0);
+        labels
+            .get()
+            .into_iter()
+            .map(|label| {
+                let font_weight = if label.kind == LabelKind::Cardinal { "bold" } else { "normal" };
+                view! {
+                    <text
+                        x={label.x}
+                        y={label.y}
+                        text-anchor="middle"
+                        dominant-baseline="central"
+                        font-family="serif"
+                        font-size={LABEL_FONT_SIZE}
+                        font-weight={font_weight}
+                        fill={palette.ink}
+                        stroke={palette.surface}
+                        stroke-width="3"
+                        paint-order="stroke"
+                        pointer-events="none"
+                        transform={format!("rotate({} {} {})", upright, label.x, label.y)}
+                    >
+                        {label.text}
+                    </text>
+                }
+            })
+            .collect_view()
+    };
+
     let tooltip = create_rw_signal(Tooltip::default());
+    let hovered = create_rw_signal::<Option<TargetId>>(None);
+    let focused = create_rw_signal::<Option<TargetId>>(None);
+
+    let svg_ref = create_node_ref::<Svg>();
+    let view_box = create_rw_signal(ViewBox::default());
+    let (plot_width, plot_height) = (width as f64, height as f64);
+    // active pointers by id, so that two touches can be read as a pinch
+    let pointers = store_value(HashMap::<i32, (f64, f64)>::new());
+
+    let plot_fraction = move |client_x: f64, client_y: f64| -> (f64, f64) {
+        let rect = svg_ref
+            .get_untracked()
+            .expect("svg exists")
+            .get_bounding_client_rect();
+        (
+            (client_x - rect.left()) / rect.width(),
+            (client_y - rect.top()) / rect.height(),
+        )
+    };
+
+    let zoom_at = move |factor: f64, fx: f64, fy: f64| {
+        view_box.update(|vb| *vb = vb.zoom_at(factor, fx, fy, plot_width, plot_height));
+    };
+
+    let on_wheel = move |ev: WheelEvent| {
+        ev.prevent_default();
+        let factor = if ev.delta_y() < 0.0 {
+            WHEEL_ZOOM_STEP
+        } else {
+            1.0 / WHEEL_ZOOM_STEP
+        };
+        let (fx, fy) = plot_fraction(ev.client_x() as f64, ev.client_y() as f64);
+        zoom_at(factor, fx, fy);
+    };
+    // on the plot itself rather than with `on:wheel`, which leptos hands to
+    // a passive listener on the window that can't stop the page scrolling
+    let mut wheel = ev::Custom::<ev::WheelEvent>::new("wheel");
+    wheel.options_mut().passive(false);
+    svg_ref.on_load(move |svg| {
+        _ = svg.on(wheel, on_wheel);
+    });
+
+    let on_pointerdown = move |ev: PointerEvent| {
+        pointers.update_value(|p| {
+            p.insert(ev.pointer_id(), (ev.client_x() as f64, ev.client_y() as f64));
+        });
+    };
+
+    let on_pointermove = move |ev: PointerEvent| {
+        let id = ev.pointer_id();
+        let current = (ev.client_x() as f64, ev.client_y() as f64);
+        let Some(previous) = pointers.with_value(|p| p.get(&id).copied()) else {
+            return;
+        };
+        let others = pointers.with_value(|p| {
+            p.iter()
+                .filter(|(other_id, _)| **other_id != id)
+                .map(|(_, pos)| *pos)
+                .collect::<Vec<_>>()
+        });
+        match others.as_slice() {
+            [] => view_box.update(|vb| {
+                *vb = vb.pan(
+                    current.0 - previous.0,
+                    current.1 - previous.1,
+                    plot_width,
+                    plot_height,
+                )
+            }),
+            [other] => {
+                let distance = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).hypot(a.1 - b.1);
+                let (before, after) = (distance(previous, *other), distance(current, *other));
+                if before > 0.0 {
+                    let (fx, fy) = plot_fraction(
+                        (current.0 + other.0) / 2.0,
+                        (current.1 + other.1) / 2.0,
+                    );
+                    zoom_at(after / before, fx, fy);
+                }
+            }
+            _ => {}
+        }
+        pointers.update_value(|p| {
+            p.insert(id, current);
+        });
+    };
+
+    let on_pointerup = move |ev: PointerEvent| {
+        pointers.update_value(|p| {
+            p.remove(&ev.pointer_id());
+        });
+    };
+
+    let on_keydown = move |ev: KeyboardEvent| {
+        let ids = objs.with(|objs| objs.iter().map(|o| o.id.clone()).collect::<Vec<_>>());
+        if ids.is_empty() {
+            return;
+        }
+        let step = |offset: isize| {
+            let n = ids.len() as isize;
+            let current = focused
+                .get_untracked()
+                .and_then(|f| ids.iter().position(|id| *id == f));
+            let next = match current {
+                Some(i) => (i as isize + offset).rem_euclid(n),
+                None if offset > 0 => 0,
+                None => n - 1,
+            };
+            focused.set(Some(ids[next as usize].clone()));
+        };
+        match ev.key().as_str() {
+            "ArrowRight" | "ArrowDown" => {
+                ev.prevent_default();
+                step(1);
+            }
+            "ArrowLeft" | "ArrowUp" => {
+                ev.prevent_default();
+                step(-1);
+            }
+            "Enter" | " " => {
+                if let Some(id) = focused.get_untracked() {
+                    ev.prevent_default();
+                    state.dispatch(Action::ToggleSelected(id));
+                }
+            }
+            "Escape" => {
+                state.dispatch(Action::Select(None));
+                focused.set(None);
+            }
+            "+" | "=" => zoom_at(WHEEL_ZOOM_STEP, 0.5, 0.5),
+            "-" => zoom_at(1.0 / WHEEL_ZOOM_STEP, 0.5, 0.5),
+            "0" => view_box.set(ViewBox::default()),
+            _ => {}
+        }
+    };
+
+    let tooltip_target = move || hovered.get().or_else(|| focused.get());
 
     let tooltip_style = move || {
         let tooltip_val = tooltip.get();
-        if let Some(current_selected) = selected.get() {
+        if tooltip_target().is_some() {
             format!(
-                "position: absolute; left: {}px; top: {}px; transform: translate(-50%, -110%);",
+                "position: absolute; left: {}px; top: {}px; transform: translate(-50%, -110%); pointer-events: none;",
                 tooltip_val.x, tooltip_val.y
             )
         } else {
@@ -244,40 +664,154 @@ pub fn PolarPlot(
         }
     };
 
-    let tooltip_text = move || {
-        let tooltip = tooltip.get();
-        tooltip.obj.map(|o| o.to_string()).unwrap_or("".to_string())
+    let tooltip_view = move || {
+        let id = tooltip.get().obj?;
+        let obj = objs.with(|objs| objs.iter().find(|o| o.id == id).cloned())?;
+        let formatter = "%H:%M";
+        let az = rad2deg(obj.az);
+        let position = format!(
+            "Az {:.1}° ({:#}), El {:.1}°",
+            az,
+            deg2cardinal(az),
+            rad2deg(obj.el)
+        );
+        let rise_set = match (obj.rising_time, obj.setting_time) {
+            (None, None) => obj.missing_crossing().to_string(),
+            (rising_time, setting_time) => format!(
+                "Rises {}, sets {}",
+                format_crossing(&obj, rising_time, formatter),
+                format_crossing(&obj, setting_time, formatter)
+            ),
+        };
+        Some(view! {
+            <div class="font-semibold">{obj.name.clone()}</div>
+            <div class="text-sm">{position}</div>
+            <div class="text-sm">{format!("Magnitude {:.2}", obj.magnitude)}</div>
+            <div class="text-sm">{rise_set}</div>
+        })
+    };
+
+    let reset_view = move || {
+        (view_box.get().zoom > 1.0).then(|| {
+            view! {
+                <button
+                    class="absolute top-0 right-0 rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong"
+                    on:click=move |_| view_box.set(ViewBox::default())
+                >
+                    "Reset view"
+                </button>
+            }
+        })
+    };
+
+    let projection_options = all::<ProjectionKind>()
+        .enumerate()
+        .map(|(i, kind)| {
+            view! {
+                <option value={i} selected={move || projection.get() == kind}>{kind.to_string()}</option>
+            }
+        })
+        .collect_view();
+
+    let on_projection_change = move |evt: web_sys::Event| {
+        let kind = event_target_value(&evt)
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| all::<ProjectionKind>().nth(i));
+        if let Some(kind) = kind {
+            projection.set(kind);
+        }
     };
 
     view! {
         <div class="content-center justify-center">
-            <svg width={width} height={height} style="display: block; margin: auto;">
-                { el_circles }
-                { az_lines }
-                <For
-                    each=move || objs.get()
-                    key=|obj| obj.name.clone()
-                    children=move |obj: AstronObjectResponse| {
-                        view! {
-                            <AstronObjectView
-                                obj=obj
-                                selected=selected
-                                tooltip=tooltip
-                                radius={radius as f64}
-                                center_x={center_x as f64}
-                                center_y={center_y as f64}
-                                width={width as f64}
-                            />
-                        }
-                    }
-                />
-            </svg>
+            <div class="flex flex-wrap items-center gap-x-4 gap-y-1 my-1">
+                <label class="flex items-center gap-x-2">
+                    <span class="font-bold">"Projection"</span>
+                    <select
+                        class="rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none"
+                        on:change=on_projection_change
+                    >
+                        {projection_options}
+                    </select>
+                </label>
+                <label class="flex items-center gap-x-2">
+                    <input
+                        type="checkbox"
+                        prop:checked={move || state.layers.get().shows(Layer::BelowHorizon)}
+                        on:change=move |_| state.dispatch(Action::ToggleLayer(Layer::BelowHorizon))
+                    />
+                    "Show objects below the horizon"
+                </label>
+                <label class="flex items-center gap-x-2">
+                    <input
+                        type="checkbox"
+                        prop:checked={move || state.layers.get().shows(Layer::Meridian)}
+                        on:change=move |_| state.dispatch(Action::ToggleLayer(Layer::Meridian))
+                    />
+                    "Show the meridian"
+                </label>
+                <PlotExport plot=svg_ref objs=objs width=width height=height/>
+            </div>
+            <div class="relative">
+                <svg
+                    node_ref={svg_ref}
+                    width={width}
+                    height={height}
+                    viewBox={move || view_box.get().to_attr(plot_width, plot_height)}
+                    tabindex="0"
+                    role="application"
+                    aria-label="Sky plot. Arrow keys cycle through objects, Enter selects, + and - zoom."
+                    class="rounded-md focus:outline-none focus-visible:ring-2 focus-visible:ring-accent"
+                    style="display: block; margin: auto; touch-action: none;"
+                    on:pointerdown=on_pointerdown
+                    on:pointermove=on_pointermove
+                    on:pointerup=on_pointerup
+                    on:pointercancel=on_pointerup
+                    on:pointerleave=on_pointerup
+                    on:keydown=on_keydown
+                    on:blur=move |_| focused.set(None)
+                >
+                    <g transform={move || format!("rotate({} {} {})", -heading.get().unwrap_or(0.0), center_x, center_y)}>
+                        { below_horizon_ring }
+                        { el_circles }
+                        { az_spokes }
+                        { meridian }
+                        { label_views }
+                        <For
+                            each=move || satellites.passes.get()
+                            key=|pass| pass.id()
+                            children=move |pass: SatellitePass| view! { <SatellitePassView sky=sky pass=pass/> }
+                        />
+                        <For
+                            each=move || objs.get()
+                            key=|obj| obj.id.clone()
+                            children=move |obj: SkyTarget| {
+                                view! {
+                                    <AstronObjectView
+                                        obj=latest_target(objs, obj)
+                                        hovered=hovered
+                                        focused=focused
+                                        tooltip=tooltip
+                                        view_box=view_box
+                                        heading=heading
+                                        sky=sky
+                                        width={width as f64}
+                                    />
+                                }
+                            }
+                        />
+                    </g>
+                </svg>
+                {reset_view}
+            </div>
             <div
                 style={tooltip_style}
-                class="text-zinc-50 rounded-md bg-zinc-500 py-1 px-2 opacity-80"
+                class="text-panel-ink rounded-md bg-panel py-1 px-2 opacity-80"
             >
-                {tooltip_text}
+                {tooltip_view}
             </div>
+            <DetailPanel objs=objs plot=svg_ref view_box=view_box heading=heading/>
         </div>
     }
 }
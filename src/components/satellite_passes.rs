@@ -0,0 +1,194 @@
+use leptos::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::HtmlInputElement;
+
+use crate::{
+    satellites::{
+        use_satellites, SatellitePass, DEFAULT_TLE_URL, MAX_ELEMENT_AGE_DAYS,
+        MAX_PREDICTION_AGE_DAYS,
+    },
+    state::use_app_state,
+    utils::{deg2cardinal, naive_utc_to_local, rad2deg},
+};
+
+const INPUT_CLASS: &str = "rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none";
+
+fn describe_point(label: &str, az: f64, el: f64) -> String {
+    let az = rad2deg(az);
+    format!("{} {:.0}° {:#}, el {:.0}°", label, az, deg2cardinal(az), rad2deg(el))
+}
+
+#[component]
+fn SatellitePassRow(pass: SatellitePass) -> impl IntoView {
+    let satellites = use_satellites();
+    let id = pass.id();
+    let is_selected = {
+        let id = id.clone();
+        move || satellites.selected.get().as_ref() == Some(&id)
+    };
+    let on_click = move |_| {
+        satellites.selected.update(|selected| {
+            *selected = if selected.as_ref() == Some(&id) { None } else { Some(id.clone()) };
+        })
+    };
+
+    let formatter = "%H:%M";
+    let times = format!(
+        "{}–{}",
+        naive_utc_to_local(&pass.start.when).format(formatter),
+        naive_utc_to_local(&pass.end.when).format(formatter)
+    );
+    let brightness = match (pass.visible, pass.magnitude) {
+        (true, Some(magnitude)) => format!("visible, magnitude {:.1}", magnitude),
+        (true, None) => "visible".to_string(),
+        (false, _) if pass.max.sunlit => "sunlit, but in daylight".to_string(),
+        (false, _) => "in the Earth's shadow".to_string(),
+    };
+
+    view! {
+        <li
+            class="cursor-pointer hover:underline"
+            class=("bg-highlight", is_selected)
+            class=("text-muted", !pass.visible)
+            on:click=on_click
+        >
+            <span class="font-medium">{pass.satellite.clone()}</span>
+            " " {times} ", "
+            {describe_point("from", pass.start.az, pass.start.el)} ", "
+            {describe_point("highest", pass.max.az, pass.max.el)} ", "
+            {describe_point("to", pass.end.az, pass.end.el)} " · "
+            {brightness}
+        </li>
+    }
+}
+
+/// Satellite passes over the next day, and where their elements come from.
+/// Clicking a pass picks out its arc on the sky plot.
+#[component]
+pub fn SatellitePasses() -> impl IntoView {
+    let satellites = use_satellites();
+    let position_time = use_app_state().position_time;
+    let visible_only = create_rw_signal(true);
+
+    let passes = move || {
+        satellites
+            .passes
+            .get()
+            .into_iter()
+            .filter(|pass| pass.visible || !visible_only.get())
+            .collect::<Vec<_>>()
+    };
+
+    // old elements still give passes, but say how far out they may be, and
+    // which satellites were left out for elements far too old to use
+    let stale = move || {
+        let when = position_time.get().1.naive_utc();
+        let stale: Vec<(String, f64)> = satellites.satellites.with(|satellites| {
+            satellites
+                .iter()
+                .map(|satellite| (satellite.name.clone(), satellite.age_days(&when)))
+                .filter(|(_, age)| age.abs() > MAX_ELEMENT_AGE_DAYS)
+                .collect()
+        });
+        let (old, dropped): (Vec<_>, Vec<_>) = stale
+            .into_iter()
+            .partition(|(_, age)| age.abs() <= MAX_PREDICTION_AGE_DAYS);
+        let describe_age = |age: f64| {
+            if age > 0.0 {
+                format!("{:.0} days old", age)
+            } else {
+                format!("from {:.0} days after the time shown", -age)
+            }
+        };
+        let oldest = |satellites: &[(String, f64)]| {
+            satellites
+                .iter()
+                .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                .map(|(name, age)| (name.clone(), describe_age(*age)))
+        };
+        let old = oldest(&old).map(|(name, age)| match old.len() {
+            1 => format!("Elements for {} are {}, so its pass times may be minutes out", name, age),
+            count => format!(
+                "Elements for {} satellites are up to {}, so their pass times may be minutes out",
+                count, age
+            ),
+        });
+        let dropped = oldest(&dropped).map(|(name, age)| match dropped.len() {
+            1 => format!("{} is left out, its elements are {}. Fetch newer ones to see its passes", name, age),
+            count => format!(
+                "{} satellites are left out, their elements are up to {}. Fetch newer ones to see their passes",
+                count, age
+            ),
+        });
+        [old, dropped]
+            .into_iter()
+            .flatten()
+            .map(|message| view! { <div class="text-muted">{message}</div> })
+            .collect_view()
+    };
+
+    let on_upload = move |evt: web_sys::Event| {
+        let input = event_target::<HtmlInputElement>(&evt);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        spawn_local(async move {
+            if let Ok(text) = JsFuture::from(file.text()).await {
+                satellites.load_text(&text.as_string().unwrap_or_default(), file.name());
+            }
+        });
+    };
+
+    view! {
+        <details>
+            <summary class="font-bold cursor-pointer">"Satellites"</summary>
+            <div class="flex flex-col gap-y-1 my-1">
+                <label class="flex items-center gap-x-2">
+                    <input
+                        type="checkbox"
+                        prop:checked={move || visible_only.get()}
+                        on:change=move |evt| visible_only.set(event_target_checked(&evt))
+                    />
+                    "Only passes that can be seen"
+                </label>
+                <Show
+                    when=move || !passes().is_empty()
+                    fallback=|| view! { <div class="text-muted">"No passes in the next 24 hours"</div> }
+                >
+                    <ul class="flex flex-col gap-y-1">
+                        <For
+                            each=passes
+                            key=|pass| pass.id()
+                            children=move |pass: SatellitePass| view! { <SatellitePassRow pass=pass/> }
+                        />
+                    </ul>
+                </Show>
+                {stale}
+                <div class="text-sm text-muted">
+                    {move || format!("Elements from {}", satellites.source.get())}
+                </div>
+                <div class="flex flex-wrap items-center gap-x-2 gap-y-1">
+                    <input
+                        type="url"
+                        aria-label="Two-Line Element endpoint"
+                        placeholder={DEFAULT_TLE_URL}
+                        class={format!("flex-1 {}", INPUT_CLASS)}
+                        prop:value={move || satellites.url.get()}
+                        on:change=move |evt| satellites.set_url.set(event_target_value(&evt))
+                    />
+                    <button
+                        class="rounded-md border border-solid py-1 px-2 border-line hover:border-line-strong"
+                        on:click=move |_| satellites.fetch()
+                    >
+                        "Fetch"
+                    </button>
+                    <label class="rounded-md border border-solid py-1 px-2 border-line hover:border-line-strong cursor-pointer">
+                        "Upload TLE file"
+                        <input type="file" accept=".tle,.txt,text/plain" class="hidden" on:change=on_upload/>
+                    </label>
+                </div>
+                {move || satellites.error.get().map(|e| view! { <div class="text-danger">{e.to_string()}</div> })}
+            </div>
+        </details>
+    }
+}
@@ -1,76 +1,81 @@
 use crate::{
-    models::AstronObjectResponse,
-    utils::{deg2cardinal, rad2deg},
-    AstronObjectsRw, SelectedRw,
+    app::latest_target,
+    models::SkyTarget,
+    state::{use_app_state, Action},
+    theme::use_theme,
+    utils::{deg2cardinal, format_crossing, rad2deg},
+    AstronObjectsRw,
 };
 use chrono::{DateTime, Local, TimeZone, Utc};
 
 use leptos::*;
 
 #[component]
-pub fn TextDisplayRow(obj: AstronObjectResponse, selected: SelectedRw) -> impl IntoView {
-    let (obj, _) = create_signal(obj);
+pub fn TextDisplayRow(obj: Memo<SkyTarget>) -> impl IntoView {
+    let theme = use_theme();
+    let state = use_app_state();
 
     let highlight = move || {
-        if let Some(name) = selected.get() {
-            return name == obj.get().name;
+        if let Some(id) = state.selected.get() {
+            return id == obj.get().id;
         }
         false
     };
 
     let az_cardinal = move || format!("{:#}", deg2cardinal(rad2deg(obj.get().az)));
     let el = move || format!("{:.2}°", rad2deg(obj.get().el));
-    let name = move || obj.get().name.to_string();
+    let name = move || obj.get().name;
+    let kind = move || obj.get().kind.to_string();
     let formatter = "%H:%M";
 
     let setting_rising_time_view = move || {
         let obj = obj.get();
-        let (setting_time, rising_time) = if obj.setting_time > obj.rising_time {
-            let rising_time_local: DateTime<Local> =
-                Utc.from_local_datetime(&obj.rising_time).unwrap().into();
-            (
+        // only the next of the two, unless one doesn't come at all
+        let (setting_time, rising_time) = match (obj.setting_time, obj.rising_time) {
+            (Some(setting_time), Some(rising_time)) if setting_time > rising_time => (
                 "-".to_string(),
-                rising_time_local.format(formatter).to_string(),
-            )
-        } else {
-            let setting_time_local: DateTime<Local> =
-                Utc.from_local_datetime(&obj.setting_time).unwrap().into();
-            (
-                setting_time_local.format(formatter).to_string(),
+                format_crossing(&obj, Some(rising_time), formatter),
+            ),
+            (Some(setting_time), Some(_)) => (
+                format_crossing(&obj, Some(setting_time), formatter),
                 "-".to_string(),
-            )
+            ),
+            (setting_time, rising_time) => (
+                format_crossing(&obj, setting_time, formatter),
+                format_crossing(&obj, rising_time, formatter),
+            ),
         };
 
+        let transit_time = obj.transit_time.map_or("-".to_string(), |transit_time| {
+            let transit_time_local: DateTime<Local> = Utc.from_local_datetime(&transit_time).unwrap().into();
+            transit_time_local.format(formatter).to_string()
+        });
+
         view! {
-            <td class="hidden sm:table-cell">
+            <td class="hidden sm:table-cell print:table-cell">
                 {setting_time}
             </td>
-            <td class="hidden sm:table-cell">
+            <td class="hidden sm:table-cell print:table-cell">
                 {rising_time}
             </td>
+            <td class="hidden sm:table-cell print:table-cell">
+                {transit_time}
+            </td>
         }
     };
 
     let magnitude = move || format!("{:.2}", obj.get().magnitude);
-    let handle_click = move |_| {
-        let obj = obj.get();
-        if let Some(current_selected) = selected.get() {
-            if current_selected != obj.name {
-                selected.set(Some(obj.name.clone()));
-            } else {
-                selected.set(None);
-            }
-        } else {
-            selected.set(Some(obj.name.clone()));
-        }
-    };
+    let handle_click = move |_| state.dispatch(Action::ToggleSelected(obj.get().id));
 
     view! {
         <tr
             on:click=handle_click
-            style:background-color=move || if highlight() {"rgb(228 228 231)"} else {"white"}
+            style:background-color=move || {
+                let palette = theme.palette();
+                if highlight() { palette.highlight } else { palette.surface }
+            }
         >
-            <td>
+            <td title={kind}>
                 {name}
             </td>
             <td>
@@ -80,7 +85,7 @@ pub fn TextDisplayRow(obj: AstronObjectResponse, selected: SelectedRw) -> impl I
                 {el}
             </td>
             {setting_rising_time_view}
-            <td class="hidden sm:table-cell">
+            <td class="hidden sm:table-cell print:table-cell">
                 {magnitude}
             </td>
         </tr>
@@ -88,7 +93,7 @@ pub fn TextDisplayRow(obj: AstronObjectResponse, selected: SelectedRw) -> impl I
 }
 
 #[component]
-pub fn TextDisplay(objs: AstronObjectsRw, selected: SelectedRw) -> impl IntoView {
+pub fn TextDisplay(objs: AstronObjectsRw) -> impl IntoView {
     view! {
         <table class="table-auto divide-y divide-solid">
             <thead>
@@ -96,18 +101,19 @@ pub fn TextDisplay(objs: AstronObjectsRw, selected: SelectedRw) -> impl IntoView
                     <th class="font-semibold text-left">"Name"</th>
                     <th class="font-semibold text-left">"Direction"</th>
                     <th class="font-semibold text-left">"Elevation"</th>
-                    <th class="font-semibold text-left hidden sm:table-cell">"Setting Time"</th>
-                    <th class="font-semibold text-left hidden sm:table-cell">"Rising Time"</th>
-                    <th class="font-semibold text-left hidden sm:table-cell">"Apparent Magnitude"</th>
+                    <th class="font-semibold text-left hidden sm:table-cell print:table-cell">"Setting Time"</th>
+                    <th class="font-semibold text-left hidden sm:table-cell print:table-cell">"Rising Time"</th>
+                    <th class="font-semibold text-left hidden sm:table-cell print:table-cell">"Transit Time"</th>
+                    <th class="font-semibold text-left hidden sm:table-cell print:table-cell">"Apparent Magnitude"</th>
                 </tr>
             </thead>
             <tbody class="divide-y divide-solid">
                 <For
                     each=move || objs.get()
-                    key=|obj| (obj.name.clone())
-                    children=move |obj: AstronObjectResponse| {
+                    key=|obj| obj.id.clone()
+                    children=move |obj: SkyTarget| {
                         view! {
-                            <TextDisplayRow obj=obj selected=selected/>
+                            <TextDisplayRow obj=latest_target(objs, obj)/>
                         }
                     }
                 />
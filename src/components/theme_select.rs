@@ -0,0 +1,37 @@
+use enum_iterator::all;
+use leptos::*;
+
+use crate::theme::{use_theme, ThemeChoice};
+
+#[component]
+pub fn ThemeSelect() -> impl IntoView {
+    let theme = use_theme();
+
+    let options = all::<ThemeChoice>()
+        .map(|choice| {
+            view! {
+                <option value={choice.to_string()} selected={move || theme.choice.get() == choice}>
+                    {choice.label()}
+                </option>
+            }
+        })
+        .collect_view();
+
+    let on_change = move |evt: web_sys::Event| {
+        if let Ok(choice) = event_target_value(&evt).parse::<ThemeChoice>() {
+            theme.set_choice.set(choice);
+        }
+    };
+
+    view! {
+        <label class="flex items-center gap-x-2">
+            <span class="font-bold">"Theme"</span>
+            <select
+                class="rounded-md border border-solid py-1 px-2 bg-surface border-line hover:border-line-strong focus:border-ink focus:outline-none"
+                on:change=on_change
+            >
+                {options}
+            </select>
+        </label>
+    }
+}
@@ -0,0 +1,46 @@
+use chrono::Local;
+use leptos::*;
+
+use crate::{clock::use_clock, coords::format_hms};
+
+/// Collapsible list of the current time as UTC, Julian date and sidereal
+/// time, for observers with setting circles.
+#[component]
+pub fn TimeSystemsPanel() -> impl IntoView {
+    let clock = use_clock();
+
+    let rows = move || {
+        let t = clock.time_systems.get();
+        [
+            ("UTC", t.utc.format("%Y-%m-%d %H:%M:%S").to_string()),
+            (
+                "Local",
+                t.utc
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S %Z")
+                    .to_string(),
+            ),
+            ("Julian date", format!("{:.5}", t.jd)),
+            ("Greenwich sidereal time", format_hms(t.gmst)),
+            ("Local sidereal time", format_hms(t.lst)),
+        ]
+        .into_iter()
+        .map(|(label, value)| {
+            view! {
+                <dt>{label}</dt>
+                <dd class="font-mono">{value}</dd>
+            }
+        })
+        .collect_view()
+    };
+
+    view! {
+        <details>
+            <summary class="font-bold cursor-pointer">
+                "Time systems"
+                {move || clock.live.get().then(|| view! { <span class="ml-2 text-sm font-normal text-muted">"live"</span> })}
+            </summary>
+            <dl class="grid grid-cols-2 gap-x-2 my-1 text-sm">{rows}</dl>
+        </details>
+    }
+}
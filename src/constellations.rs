@@ -0,0 +1,43 @@
+use crate::coords::{equatorial_to_ecliptic, julian_centuries, mean_obliquity};
+
+/// Ecliptic longitudes (J2000, degrees) at which the ecliptic crosses into
+/// each constellation, following the IAU boundaries.
+const ECLIPTIC_CONSTELLATIONS: [(f64, &str); 13] = [
+    (29.1, "Aries"),
+    (53.5, "Taurus"),
+    (90.4, "Gemini"),
+    (118.3, "Cancer"),
+    (138.2, "Leo"),
+    (174.2, "Virgo"),
+    (218.0, "Libra"),
+    (241.1, "Scorpius"),
+    (248.0, "Ophiuchus"),
+    (266.3, "Sagittarius"),
+    (299.7, "Capricornus"),
+    (327.9, "Aquarius"),
+    (351.6, "Pisces"),
+];
+
+/// How far from the ecliptic the lookup table can be trusted, in degrees.
+/// The Moon and planets stay well within this.
+const MAX_ECLIPTIC_LATITUDE: f64 = 8.0;
+
+/// Constellation containing the given apparent right ascension and
+/// declination (radians) at julian date `jd`. Only positions close to the
+/// ecliptic can be resolved; anything else gives `None`.
+pub fn constellation(ra: f64, dec: f64, jd: f64) -> Option<&'static str> {
+    let (lon, lat) = equatorial_to_ecliptic(ra, dec, mean_obliquity(jd));
+    if lat.to_degrees().abs() > MAX_ECLIPTIC_LATITUDE {
+        return None;
+    }
+    // undo general precession in longitude to get back to J2000
+    let lon_j2000 = (lon.to_degrees() - 1.396971 * julian_centuries(jd)).rem_euclid(360.0);
+    let name = ECLIPTIC_CONSTELLATIONS
+        .iter()
+        .rev()
+        .find(|(start, _)| lon_j2000 >= *start)
+        .map(|(_, name)| *name)
+        // before Aries' boundary we are still in Pisces
+        .unwrap_or("Pisces");
+    Some(name)
+}
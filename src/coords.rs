@@ -0,0 +1,486 @@
+use std::f64::consts::{PI, TAU};
+
+use chrono::NaiveDateTime;
+
+use crate::errors::AppError;
+
+pub const J2000: f64 = 2451545.0;
+
+/// J2000 right ascension and declination of the north galactic pole, and
+/// the galactic longitude of the north celestial pole, in degrees
+const GALACTIC_POLE: (f64, f64, f64) = (192.85948, 27.12825, 122.93192);
+
+fn galactic_pole() -> (f64, f64, f64) {
+    let (ra, dec, l) = GALACTIC_POLE;
+    (ra.to_radians(), dec.to_radians(), l.to_radians())
+}
+
+fn arcsec(x: f64) -> f64 {
+    (x / 3600.0).to_radians()
+}
+
+/// Julian date of a naive UTC datetime
+pub fn julian_date(dt: &NaiveDateTime) -> f64 {
+    dt.and_utc().timestamp_millis() as f64 / 86_400_000.0 + 2440587.5
+}
+
+/// Julian centuries since J2000.0
+pub fn julian_centuries(jd: f64) -> f64 {
+    (jd - J2000) / 36525.0
+}
+
+/// Greenwich mean sidereal time in radians (Meeus, eq. 12.4)
+pub fn gmst(jd: f64) -> f64 {
+    let t = julian_centuries(jd);
+    let deg = 280.46061837 + 360.98564736629 * (jd - J2000) + 0.000387933 * t * t
+        - t * t * t / 38710000.0;
+    deg.to_radians().rem_euclid(TAU)
+}
+
+/// Greenwich apparent sidereal time in radians, mean sidereal time corrected
+/// for nutation
+#[allow(dead_code)]
+pub fn gast(jd: f64) -> f64 {
+    let (d_psi, d_eps) = nutation(jd);
+    (gmst(jd) + d_psi * (mean_obliquity(jd) + d_eps).cos()).rem_euclid(TAU)
+}
+
+/// local mean sidereal time in radians for an east positive longitude in radians
+pub fn local_sidereal_time(jd: f64, lon: f64) -> f64 {
+    (gmst(jd) + lon).rem_euclid(TAU)
+}
+
+/// mean obliquity of the ecliptic in radians (Meeus, eq. 22.2)
+pub fn mean_obliquity(jd: f64) -> f64 {
+    let t = julian_centuries(jd);
+    let arcsec = 21.448 - 46.8150 * t - 0.00059 * t * t + 0.001813 * t * t * t;
+    (23.0 + 26.0 / 60.0 + arcsec / 3600.0).to_radians()
+}
+
+/// Convert right ascension and declination to azimuth (measured from north
+/// through east) and elevation. All angles are in radians.
+pub fn equatorial_to_horizontal(ra: f64, dec: f64, lat: f64, lst: f64) -> (f64, f64) {
+    let ha = lst - ra;
+    let el = (lat.sin() * dec.sin() + lat.cos() * dec.cos() * ha.cos()).asin();
+    let az = (-dec.cos() * ha.sin()).atan2(dec.sin() * lat.cos() - dec.cos() * lat.sin() * ha.cos());
+    (az.rem_euclid(TAU), el)
+}
+
+/// Convert azimuth (from north through east) and elevation back to right
+/// ascension and declination. All angles are in radians.
+#[allow(dead_code)]
+pub fn horizontal_to_equatorial(az: f64, el: f64, lat: f64, lst: f64) -> (f64, f64) {
+    let dec = (lat.sin() * el.sin() + lat.cos() * el.cos() * az.cos()).asin();
+    let ha = (-az.sin() * el.cos()).atan2(lat.cos() * el.sin() - lat.sin() * el.cos() * az.cos());
+    ((lst - ha).rem_euclid(TAU), dec)
+}
+
+/// Convert right ascension and declination to ecliptic longitude and latitude
+pub fn equatorial_to_ecliptic(ra: f64, dec: f64, obliquity: f64) -> (f64, f64) {
+    let lat = (dec.sin() * obliquity.cos() - dec.cos() * obliquity.sin() * ra.sin()).asin();
+    let lon = (ra.sin() * obliquity.cos() + dec.tan() * obliquity.sin()).atan2(ra.cos());
+    (lon.rem_euclid(TAU), lat)
+}
+
+/// Convert ecliptic longitude and latitude to right ascension and
+/// declination
+pub fn ecliptic_to_equatorial(lon: f64, lat: f64, obliquity: f64) -> (f64, f64) {
+    let dec = (lat.sin() * obliquity.cos() + lat.cos() * obliquity.sin() * lon.sin()).asin();
+    let ra = (lon.sin() * obliquity.cos() - lat.tan() * obliquity.sin()).atan2(lon.cos());
+    (ra.rem_euclid(TAU), dec)
+}
+
+/// Precess a right ascension and declination from the mean equator and
+/// equinox of `jd_from` to those of `jd_to` (Meeus, eq. 21.2 and 21.4)
+pub fn precess(ra: f64, dec: f64, jd_from: f64, jd_to: f64) -> (f64, f64) {
+    let big_t = julian_centuries(jd_from);
+    let t = (jd_to - jd_from) / 36525.0;
+    let rate = 2306.2181 + 1.39656 * big_t - 0.000139 * big_t * big_t;
+    let zeta = arcsec(rate * t + (0.30188 - 0.000344 * big_t) * t * t + 0.017998 * t * t * t);
+    let z = arcsec(rate * t + (1.09468 + 0.000066 * big_t) * t * t + 0.018203 * t * t * t);
+    let theta = arcsec(
+        (2004.3109 - 0.85330 * big_t - 0.000217 * big_t * big_t) * t
+            - (0.42665 + 0.000217 * big_t) * t * t
+            - 0.041833 * t * t * t,
+    );
+    let a = dec.cos() * (ra + zeta).sin();
+    let b = theta.cos() * dec.cos() * (ra + zeta).cos() - theta.sin() * dec.sin();
+    let c = theta.sin() * dec.cos() * (ra + zeta).cos() + theta.cos() * dec.sin();
+    ((a.atan2(b) + z).rem_euclid(TAU), c.asin())
+}
+
+/// Precess from the mean equator and equinox of J2000.0 to those of `jd`
+pub fn precess_from_j2000(ra: f64, dec: f64, jd: f64) -> (f64, f64) {
+    precess(ra, dec, J2000, jd)
+}
+
+/// Nutation in longitude and in obliquity in radians, to about 0.5″ and
+/// 0.1″ (Meeus, chapter 22)
+#[allow(dead_code)]
+pub fn nutation(jd: f64) -> (f64, f64) {
+    let t = julian_centuries(jd);
+    let omega = (125.04452 - 1934.136261 * t).to_radians();
+    let sun = (280.4665 + 36000.7698 * t).to_radians();
+    let moon = (218.3165 + 481267.8813 * t).to_radians();
+    let d_psi = -17.20 * omega.sin() - 1.32 * (2.0 * sun).sin() - 0.23 * (2.0 * moon).sin()
+        + 0.21 * (2.0 * omega).sin();
+    let d_eps = 9.20 * omega.cos() + 0.57 * (2.0 * sun).cos() + 0.10 * (2.0 * moon).cos()
+        - 0.09 * (2.0 * omega).cos();
+    (arcsec(d_psi), arcsec(d_eps))
+}
+
+/// Move a mean right ascension and declination of date to the true equator
+/// and equinox, adding nutation (Meeus, eq. 23.1)
+#[allow(dead_code)]
+pub fn apply_nutation(ra: f64, dec: f64, jd: f64) -> (f64, f64) {
+    let (d_psi, d_eps) = nutation(jd);
+    let eps = mean_obliquity(jd) + d_eps;
+    let d_ra = (eps.cos() + eps.sin() * ra.sin() * dec.tan()) * d_psi - ra.cos() * dec.tan() * d_eps;
+    let d_dec = eps.sin() * ra.cos() * d_psi + ra.sin() * d_eps;
+    ((ra + d_ra).rem_euclid(TAU), dec + d_dec)
+}
+
+/// Convert J2000 right ascension and declination to galactic longitude and
+/// latitude
+pub fn equatorial_to_galactic(ra: f64, dec: f64) -> (f64, f64) {
+    let (ra_pole, dec_pole, l_ncp) = galactic_pole();
+    let b = (dec.sin() * dec_pole.sin() + dec.cos() * dec_pole.cos() * (ra - ra_pole).cos()).asin();
+    let y = dec.cos() * (ra - ra_pole).sin();
+    let x = dec.sin() * dec_pole.cos() - dec.cos() * dec_pole.sin() * (ra - ra_pole).cos();
+    ((l_ncp - y.atan2(x)).rem_euclid(TAU), b)
+}
+
+/// Convert galactic longitude and latitude to J2000 right ascension and
+/// declination
+#[allow(dead_code)]
+pub fn galactic_to_equatorial(l: f64, b: f64) -> (f64, f64) {
+    let (ra_pole, dec_pole, l_ncp) = galactic_pole();
+    let dec = (b.sin() * dec_pole.sin() + b.cos() * dec_pole.cos() * (l_ncp - l).cos()).asin();
+    let y = b.cos() * (l_ncp - l).sin();
+    let x = b.sin() * dec_pole.cos() - b.cos() * dec_pole.sin() * (l_ncp - l).cos();
+    ((ra_pole + y.atan2(x)).rem_euclid(TAU), dec)
+}
+
+/// great circle distance between two points on the sphere, in radians
+pub fn angular_separation(ra1: f64, dec1: f64, ra2: f64, dec2: f64) -> f64 {
+    let d_ra = ra2 - ra1;
+    let x = (dec2.cos() * d_ra.sin()).hypot(dec1.cos() * dec2.sin() - dec1.sin() * dec2.cos() * d_ra.cos());
+    let y = dec1.sin() * dec2.sin() + dec1.cos() * dec2.cos() * d_ra.cos();
+    x.atan2(y)
+}
+
+/// Format an angle in radians as hours, minutes and seconds, eg `12h 34m 56.7s`
+pub fn format_hms(angle: f64) -> String {
+    let hours = angle.rem_euclid(TAU) * 12.0 / PI;
+    let tenths = (hours * 36000.0).round() as i64 % (24 * 36000);
+    format!(
+        "{:02}h {:02}m {:04.1}s",
+        tenths / 36000,
+        (tenths / 600) % 60,
+        (tenths % 600) as f64 / 10.0
+    )
+}
+
+/// Format an angle in radians as signed degrees, arcminutes and arcseconds,
+/// eg `+12° 34′ 56″`
+pub fn format_dms(angle: f64) -> String {
+    let sign = if angle < 0.0 { '-' } else { '+' };
+    let arcsec = (angle.abs().to_degrees() * 3600.0).round() as i64;
+    format!(
+        "{}{:02}° {:02}′ {:02}″",
+        sign,
+        arcsec / 3600,
+        (arcsec / 60) % 60,
+        arcsec % 60
+    )
+}
+
+/// Read `12 34 56.7`, `12:34:56.7`, `12h34m56.7s`, `-12° 34′ 56″` or plain
+/// `12.58` as a signed number of units
+fn parse_sexagesimal(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (sign, rest) = match text.strip_prefix('-').or_else(|| text.strip_prefix('−')) {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let parts = rest
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+    let mut value = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        let number = part.parse::<f64>().ok()?;
+        // only the last part may have a fraction, and minutes and seconds
+        // stay under 60
+        if (i + 1 < parts.len() && part.contains('.')) || (i > 0 && number >= 60.0) {
+            return None;
+        }
+        value += number / 60f64.powi(i as i32);
+    }
+    Some(sign * value)
+}
+
+/// Parse a right ascension in hours, minutes and seconds, or decimal hours,
+/// to radians
+pub fn parse_hms(text: &str) -> Result<f64, AppError> {
+    parse_sexagesimal(text)
+        .filter(|hours| (0.0..24.0).contains(hours))
+        .map(|hours| (hours * 15.0).to_radians())
+        .ok_or_else(|| AppError::ParseError(format!("Can't read \"{}\" as a right ascension", text)))
+}
+
+/// Parse a declination in degrees, arcminutes and arcseconds, or decimal
+/// degrees, to radians
+pub fn parse_dms(text: &str) -> Result<f64, AppError> {
+    parse_sexagesimal(text)
+        .filter(|deg| (-90.0..=90.0).contains(deg))
+        .map(f64::to_radians)
+        .ok_or_else(|| AppError::ParseError(format!("Can't read \"{}\" as a declination", text)))
+}
+
+/// How to write geographic coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordinateNotation {
+    /// `52.52°N`
+    #[default]
+    Decimal,
+    /// `52° 31′ 12″ N`
+    Dms,
+}
+
+fn format_geographic(deg: f64, positive: char, negative: char, notation: CoordinateNotation) -> String {
+    let hemisphere = if deg < 0.0 { negative } else { positive };
+    match notation {
+        CoordinateNotation::Decimal => format!("{:.2}°{}", deg.abs(), hemisphere),
+        CoordinateNotation::Dms => {
+            let arcsec = (deg.abs() * 3600.0).round() as i64;
+            format!(
+                "{}° {:02}′ {:02}″ {}",
+                arcsec / 3600,
+                (arcsec / 60) % 60,
+                arcsec % 60,
+                hemisphere
+            )
+        }
+    }
+}
+
+/// Format a latitude and longitude in degrees with hemisphere letters,
+/// eg `33.87°S, 151.21°E`
+pub fn format_lat_lon(lat: f64, lon: f64, notation: CoordinateNotation) -> String {
+    format!(
+        "{}, {}",
+        format_geographic(lat, 'N', 'S', notation),
+        format_geographic(lon, 'E', 'W', notation)
+    )
+}
+
+/// Low precision apparent ecliptic longitude of the Sun in radians and its
+/// distance in AU (Astronomical Almanac, section C).
+pub fn sun_ecliptic(jd: f64) -> (f64, f64) {
+    let n = jd - J2000;
+    let l = 280.460 + 0.9856474 * n;
+    let g = (357.528 + 0.9856003 * n).to_radians();
+    let lambda = (l + 1.915 * g.sin() + 0.020 * (2.0 * g).sin()).to_radians();
+    let distance = 1.00014 - 0.01671 * g.cos() - 0.00014 * (2.0 * g).cos();
+    (lambda.rem_euclid(TAU), distance)
+}
+
+/// Low precision apparent right ascension and declination of the Sun in
+/// radians, good to about 0.01° (Astronomical Almanac, section C).
+pub fn sun_equatorial(jd: f64) -> (f64, f64) {
+    let n = jd - J2000;
+    let (lambda, _) = sun_ecliptic(jd);
+    let epsilon = (23.439 - 0.0000004 * n).to_radians();
+    let ra = (epsilon.cos() * lambda.sin()).atan2(lambda.cos());
+    let dec = (epsilon.sin() * lambda.sin()).asin();
+    (ra.rem_euclid(TAU), dec)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn hms(h: f64, m: f64, s: f64) -> f64 {
+        ((h + m / 60.0 + s / 3600.0) * 15.0).to_radians()
+    }
+
+    fn dms(d: f64, m: f64, s: f64) -> f64 {
+        d.signum() * (d.abs() + m / 60.0 + s / 3600.0).to_radians()
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    /// 1987 April 10, 19:21:00 UT, the instant of Meeus' examples 12.b and 13.b
+    fn meeus_instant() -> f64 {
+        let when = NaiveDate::from_ymd_opt(1987, 4, 10)
+            .unwrap()
+            .and_hms_opt(19, 21, 0)
+            .unwrap();
+        julian_date(&when)
+    }
+
+    #[test]
+    fn julian_date_of_j2000() {
+        let when = NaiveDate::from_ymd_opt(2000, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(julian_date(&when), J2000);
+    }
+
+    #[test]
+    fn sidereal_time() {
+        // Meeus, examples 12.a and 12.b
+        assert_close(gmst(2446895.5), hms(13.0, 10.0, 46.3668), arcsec(0.01));
+        assert_close(gmst(meeus_instant()), hms(8.0, 34.0, 57.0896), arcsec(0.01));
+        // apparent, Meeus 12.a: 13h 10m 46.1351s
+        assert_close(gast(2446895.5), hms(13.0, 10.0, 46.1351), arcsec(0.2));
+        // Washington, 77° 03′ 56″ W
+        let lst = local_sidereal_time(meeus_instant(), -dms(77.0, 3.0, 56.0));
+        assert_close(lst, hms(8.0, 34.0, 57.0896) - dms(77.0, 3.0, 56.0), arcsec(0.01));
+    }
+
+    #[test]
+    fn obliquity_at_j2000() {
+        // Astronomical Almanac: 23° 26′ 21.448″
+        assert_close(mean_obliquity(J2000), dms(23.0, 26.0, 21.448), arcsec(0.001));
+    }
+
+    #[test]
+    fn horizontal_and_back() {
+        // Venus from Washington, Meeus example 13.b; Meeus measures azimuth
+        // from the south, so 68.0337° becomes 248.0337°
+        let (ra, dec) = (hms(23.0, 9.0, 16.641), dms(-6.0, 43.0, 11.61));
+        let lat = dms(38.0, 55.0, 17.0);
+        let lst = hms(8.0, 34.0, 56.853) - dms(77.0, 3.0, 56.0);
+        let (az, el) = equatorial_to_horizontal(ra, dec, lat, lst);
+        assert_close(az, 248.0337f64.to_radians(), 1e-5);
+        assert_close(el, 15.1249f64.to_radians(), 1e-5);
+
+        let (ra2, dec2) = horizontal_to_equatorial(az, el, lat, lst);
+        assert_close(ra2, ra, 1e-10);
+        assert_close(dec2, dec, 1e-10);
+    }
+
+    #[test]
+    fn ecliptic_and_back() {
+        // Pollux, Meeus example 13.a
+        let (ra, dec) = (116.328942f64.to_radians(), 28.026183f64.to_radians());
+        let obliquity = 23.4392911f64.to_radians();
+        let (lon, lat) = equatorial_to_ecliptic(ra, dec, obliquity);
+        assert_close(lon, 113.215630f64.to_radians(), 1e-7);
+        assert_close(lat, 6.684170f64.to_radians(), 1e-7);
+
+        let (ra2, dec2) = ecliptic_to_equatorial(lon, lat, obliquity);
+        assert_close(ra2, ra, 1e-10);
+        assert_close(dec2, dec, 1e-10);
+    }
+
+    #[test]
+    fn galactic_and_back() {
+        // the galactic centre and north pole, as defined for J2000
+        let (l, b) = equatorial_to_galactic(266.40499f64.to_radians(), (-28.93617f64).to_radians());
+        assert_close((l + PI).rem_euclid(TAU) - PI, 0.0, 1e-5);
+        assert_close(b, 0.0, 1e-5);
+        let (_, b) = equatorial_to_galactic(192.85948f64.to_radians(), 27.12825f64.to_radians());
+        assert_close(b, PI / 2.0, 1e-10);
+
+        let (ra, dec) = (1.234, -0.567);
+        let (l, b) = equatorial_to_galactic(ra, dec);
+        let (ra2, dec2) = galactic_to_equatorial(l, b);
+        assert_close(ra2, ra, 1e-10);
+        assert_close(dec2, dec, 1e-10);
+    }
+
+    #[test]
+    fn precession() {
+        // θ Persei, Meeus example 21.b, with its proper motion already
+        // applied to the J2000 position
+        let (ra, dec) = (hms(2.0, 44.0, 12.9747), dms(49.0, 13.0, 39.896));
+        let jd = 2462088.69;
+        let (ra_date, dec_date) = precess_from_j2000(ra, dec, jd);
+        assert_close(ra_date, hms(2.0, 46.0, 11.331), arcsec(0.1));
+        assert_close(dec_date, dms(49.0, 20.0, 54.54), arcsec(0.1));
+
+        let (ra2, dec2) = precess(ra_date, dec_date, jd, J2000);
+        assert_close(ra2, ra, arcsec(0.01));
+        assert_close(dec2, dec, arcsec(0.01));
+    }
+
+    #[test]
+    fn nutation_in_longitude_and_obliquity() {
+        // Meeus example 22.a: Δψ = -3.788″, Δε = +9.443″
+        let (d_psi, d_eps) = nutation(2446895.5);
+        assert_close(d_psi, arcsec(-3.788), arcsec(0.5));
+        assert_close(d_eps, arcsec(9.443), arcsec(0.1));
+    }
+
+    #[test]
+    fn nutation_in_equatorial() {
+        // θ Persei, Meeus example 23.a: Δα1 = +15.843″, Δδ1 = +6.218″
+        let (ra, dec) = (41.5472f64.to_radians(), 49.3485f64.to_radians());
+        let (ra2, dec2) = apply_nutation(ra, dec, 2462088.69);
+        assert_close(ra2 - ra, arcsec(15.843), arcsec(1.0));
+        assert_close(dec2 - dec, arcsec(6.218), arcsec(0.5));
+    }
+
+    #[test]
+    fn separation() {
+        // Arcturus and Spica, Meeus example 17.a: 32.7930°
+        let separation = angular_separation(
+            213.9154f64.to_radians(),
+            19.1825f64.to_radians(),
+            201.2983f64.to_radians(),
+            (-11.1614f64).to_radians(),
+        );
+        assert_close(separation, 32.7930f64.to_radians(), 1e-5);
+    }
+
+    #[test]
+    fn sexagesimal_parsing() {
+        let ra = hms(5.0, 55.0, 10.3);
+        for text in ["05 55 10.3", "5:55:10.3", "5h55m10.3s", " 05h 55m 10.3s "] {
+            assert_close(parse_hms(text).unwrap(), ra, 1e-12);
+        }
+        assert_close(parse_hms("5.9195").unwrap(), (5.9195f64 * 15.0).to_radians(), 1e-12);
+
+        let dec = dms(7.0, 24.0, 25.0);
+        for text in ["+07 24 25", "7°24′25″", "07:24:25", "7d24'25\""] {
+            assert_close(parse_dms(text).unwrap(), dec, 1e-12);
+        }
+        // the sign belongs to the whole angle, not just the degrees
+        assert_close(parse_dms("-00 30 00").unwrap(), (-0.5f64).to_radians(), 1e-12);
+        assert_close(parse_dms("−0:30").unwrap(), (-0.5f64).to_radians(), 1e-12);
+
+        for text in ["", "abc", "24 00 00", "5 60 00", "5.5 30", "1 2 3 4"] {
+            assert!(parse_hms(text).is_err(), "{}", text);
+        }
+        for text in ["", "+91", "45 00 60", "1 2 3 4"] {
+            assert!(parse_dms(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn sexagesimal_formatting() {
+        assert_eq!(format_hms(hms(5.0, 55.0, 10.3)), "05h 55m 10.3s");
+        assert_eq!(format_dms(dms(-7.0, 24.0, 25.0)), "-07° 24′ 25″");
+        assert_eq!(format_hms(hms(23.0, 59.0, 59.99)), "00h 00m 00.0s");
+
+        let ra = hms(12.0, 34.0, 56.7);
+        assert_close(parse_hms(&format_hms(ra)).unwrap(), ra, arcsec(0.01));
+    }
+}
@@ -0,0 +1,201 @@
+use std::f64::consts::TAU;
+
+use chrono::{Duration, NaiveDateTime};
+use leptos::*;
+use leptos_use::storage::{use_local_storage, JsonCodec};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    almanac::crossing,
+    coords::{
+        equatorial_to_horizontal, julian_date, local_sidereal_time, precess_from_j2000, J2000,
+    },
+    errors::AppError,
+    models::{Position, SkyTarget, TargetId, TargetKind},
+    state::use_app_state,
+    AstronObjectsRw,
+};
+
+const CUSTOM_TARGETS_STORAGE_KEY: &str = "planet-tracker-custom-targets";
+
+/// how far ahead rising, transit and setting are looked for
+const RISE_SET_WINDOW_HOURS: i64 = 24;
+/// mean sidereal day, in hours
+const SIDEREAL_DAY_HOURS: f64 = 23.9344696;
+const DAYS_PER_JULIAN_YEAR: f64 = 365.25;
+
+fn mas_to_radians(mas: f64) -> f64 {
+    (mas / 3_600_000.0).to_radians()
+}
+
+/// A star or anything else fixed on the sky, from its J2000 catalogue
+/// position. Angles are in radians.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomTarget {
+    pub name: String,
+    pub ra: f64,
+    pub dec: f64,
+    /// proper motion in right ascension times cos(dec), milliarcseconds a
+    /// year, as catalogues give it
+    #[serde(default)]
+    pub pm_ra: f64,
+    /// proper motion in declination, milliarcseconds a year
+    #[serde(default)]
+    pub pm_dec: f64,
+    pub magnitude: f64,
+}
+
+impl CustomTarget {
+    pub fn id(&self) -> TargetId {
+        TargetId::new(TargetKind::Custom, &self.name)
+    }
+
+    /// right ascension and declination of date, moved by proper motion
+    /// from J2000.0 and then precessed
+    pub fn equatorial(&self, when: &NaiveDateTime) -> (f64, f64) {
+        let jd = julian_date(when);
+        let years = (jd - J2000) / DAYS_PER_JULIAN_YEAR;
+        // at the pole right ascension is undefined and so is its rate
+        let ra = if self.dec.cos() > f64::EPSILON {
+            self.ra + mas_to_radians(self.pm_ra) / self.dec.cos() * years
+        } else {
+            self.ra
+        };
+        let dec = self.dec + mas_to_radians(self.pm_dec) * years;
+        precess_from_j2000(ra, dec, jd)
+    }
+
+    fn horizontal(&self, when: &NaiveDateTime, position: &Position) -> (f64, f64) {
+        let (ra, dec) = self.equatorial(when);
+        let lst = local_sidereal_time(julian_date(when), position.lon.to_radians());
+        equatorial_to_horizontal(ra, dec, position.lat.to_radians(), lst)
+    }
+
+    /// next time the target crosses the meridian
+    fn transit(&self, when: NaiveDateTime, position: &Position) -> NaiveDateTime {
+        let (ra, _) = self.equatorial(&when);
+        let lst = local_sidereal_time(julian_date(&when), position.lon.to_radians());
+        let hours = (ra - lst).rem_euclid(TAU) / TAU * SIDEREAL_DAY_HOURS;
+        when + Duration::milliseconds((hours * 3_600_000.0) as i64)
+    }
+
+    /// The target as listed and plotted alongside the planets
+    pub fn target(&self, position: &Position, when: NaiveDateTime) -> SkyTarget {
+        let (ra, dec) = self.equatorial(&when);
+        let (az, el) = self.horizontal(&when, position);
+        let end = when + Duration::hours(RISE_SET_WINDOW_HOURS);
+        let altitude = |t: &NaiveDateTime| self.horizontal(t, position).1.to_degrees();
+        SkyTarget {
+            id: self.id(),
+            kind: TargetKind::Custom,
+            name: self.name.clone(),
+            color: None,
+            body: None,
+            magnitude: self.magnitude,
+            size: 0.0,
+            az,
+            el,
+            ra,
+            dec,
+            setting_time: crossing(altitude, when, end, 0.0, true),
+            rising_time: crossing(altitude, when, end, 0.0, false),
+            transit_time: Some(self.transit(when, position)),
+            when,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CustomTargets {
+    pub targets: Signal<Vec<CustomTarget>>,
+    pub set_targets: WriteSignal<Vec<CustomTarget>>,
+    pub error: RwSignal<Option<AppError>>,
+}
+
+impl CustomTargets {
+    /// Add `target`, replacing any target with the same id.
+    pub fn add(&self, target: CustomTarget) {
+        if target.name.trim().is_empty() {
+            return self.error.set(Some(AppError::ParseError(
+                "The target needs a name".to_string(),
+            )));
+        }
+        if !(-90.0..=90.0).contains(&target.dec.to_degrees()) {
+            return self.error.set(Some(AppError::ParseError(
+                "Declination must be between -90° and +90°".to_string(),
+            )));
+        }
+        if target.dec.cos() <= f64::EPSILON && target.pm_ra != 0.0 {
+            return self.error.set(Some(AppError::ParseError(
+                "A target at the pole can't have proper motion in right ascension".to_string(),
+            )));
+        }
+        self.error.set(None);
+        let id = target.id();
+        self.set_targets.update(|targets| {
+            targets.retain(|t| t.id() != id);
+            targets.push(target);
+        });
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.set_targets
+            .update(|targets| targets.retain(|t| t.name != name));
+    }
+}
+
+/// Keep user-defined targets in local storage and their positions in
+/// `objs`, next to the server's planets.
+pub fn use_custom_targets(objs: AstronObjectsRw) -> CustomTargets {
+    let position_time = use_app_state().position_time;
+    let (targets, set_targets, _) =
+        use_local_storage::<Vec<CustomTarget>, JsonCodec>(CUSTOM_TARGETS_STORAGE_KEY);
+
+    create_effect(move |_| {
+        let (position, when) = position_time.get();
+        let sky_targets: Vec<SkyTarget> = targets.with(|targets| {
+            targets
+                .iter()
+                .map(|target| target.target(&position, when.naive_utc()))
+                .collect()
+        });
+        objs.update(|objs| {
+            objs.retain(|obj| obj.kind != TargetKind::Custom);
+            objs.extend(sky_targets);
+        });
+    });
+
+    CustomTargets {
+        targets,
+        set_targets,
+        error: create_rw_signal(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn pole_ignores_proper_motion_in_right_ascension() {
+        let target = CustomTarget {
+            name: "Pole".to_string(),
+            ra: 0.0,
+            dec: 90f64.to_radians(),
+            pm_ra: 100.0,
+            pm_dec: 0.0,
+            magnitude: 0.0,
+        };
+        let when = NaiveDate::from_ymd_opt(2050, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let still = CustomTarget {
+            pm_ra: 0.0,
+            ..target.clone()
+        };
+        assert_eq!(target.equatorial(&when), still.equatorial(&when));
+    }
+}
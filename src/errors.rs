@@ -3,11 +3,13 @@ use std::fmt;
 use serde::{Serialize, Deserialize};
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::enum_variant_names)]
 pub enum AppError {
     FetchError(String),
     JsonError(String),
     DomError(String),
+    ParseError(String),
 }
 
 impl fmt::Display for AppError {
@@ -16,6 +18,7 @@ impl fmt::Display for AppError {
             Self::FetchError(s) => write!(f, "{}", s),
             Self::JsonError(s) => write!(f, "{}", s),
             Self::DomError(s) => write!(f, "{}", s),
+            Self::ParseError(s) => write!(f, "{}", s),
         }
     }
 }
@@ -0,0 +1,244 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use futures::channel::oneshot;
+use leptos::*;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{
+    Blob, BlobPropertyBag, CanvasRenderingContext2d, Element, HtmlAnchorElement,
+    HtmlCanvasElement, HtmlImageElement, Url, XmlSerializer,
+};
+
+use crate::{errors::AppError, label_layout::estimate_text_width, theme::Palette};
+
+const CAPTION_FONT_SIZE: f64 = 12.0;
+const CAPTION_LINE_HEIGHT: f64 = 18.0;
+const CAPTION_PADDING: f64 = 8.0;
+const LEGEND_SWATCH: f64 = 5.0;
+/// how long an object url outlives its last use, so that Firefox and Safari
+/// can finish reading it for a download
+const REVOKE_DELAY_MS: u64 = 1000;
+
+/// computed style properties copied onto every exported element, so the
+/// image looks the same without the page's stylesheets
+const INLINED_PROPERTIES: [&str; 10] = [
+    "fill",
+    "stroke",
+    "stroke-width",
+    "stroke-dasharray",
+    "opacity",
+    "visibility",
+    "display",
+    "font-family",
+    "font-size",
+    "font-weight",
+];
+
+/// Text shown under an exported plot so that it stands on its own.
+#[derive(Debug, Clone)]
+pub struct Caption {
+    pub location: String,
+    pub time: String,
+    /// object names and their colours
+    pub legend: Vec<(String, String)>,
+}
+
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// svg markup for `caption` laid out to `width`, and the height it takes
+pub fn caption_markup(caption: &Caption, width: f64, palette: &Palette) -> (String, f64) {
+    let text = |x: f64, y: f64, weight: &str, content: &str| {
+        format!(
+            r#"<text x="{}" y="{}" font-family="serif" font-size="{}" font-weight="{}" fill="{}">{}</text>"#,
+            x,
+            y,
+            CAPTION_FONT_SIZE,
+            weight,
+            palette.ink,
+            xml_escape(content)
+        )
+    };
+    let mut y = CAPTION_PADDING + CAPTION_FONT_SIZE;
+    let mut markup = text(CAPTION_PADDING, y, "bold", &caption.location);
+    y += CAPTION_LINE_HEIGHT;
+    markup.push_str(&text(CAPTION_PADDING, y, "normal", &caption.time));
+    y += CAPTION_LINE_HEIGHT;
+
+    let mut x = CAPTION_PADDING;
+    for (name, colour) in &caption.legend {
+        let item_width = 3.0 * LEGEND_SWATCH + estimate_text_width(name, CAPTION_FONT_SIZE) + 12.0;
+        if x + item_width > width - CAPTION_PADDING && x > CAPTION_PADDING {
+            x = CAPTION_PADDING;
+            y += CAPTION_LINE_HEIGHT;
+        }
+        markup.push_str(&format!(
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}" stroke="{}" stroke-width="0.5"/>"#,
+            x + LEGEND_SWATCH,
+            y - CAPTION_FONT_SIZE / 3.0,
+            LEGEND_SWATCH,
+            colour,
+            palette.ink
+        ));
+        markup.push_str(&text(x + 3.0 * LEGEND_SWATCH, y, "normal", name));
+        x += item_width;
+    }
+    (markup, y + CAPTION_PADDING)
+}
+
+/// Copy computed styles from `source` onto its deep clone `target`, element by element.
+fn inline_styles(source: &Element, target: &Element) -> Result<(), AppError> {
+    let dom_error = |_| AppError::DomError("Couldn't inline plot styles".to_string());
+    let sources = source.query_selector_all("*").map_err(dom_error)?;
+    let targets = target.query_selector_all("*").map_err(dom_error)?;
+    for i in 0..sources.length() {
+        let (Some(from), Some(to)) = (sources.item(i), targets.item(i)) else {
+            continue;
+        };
+        let (Ok(from), Ok(to)) = (from.dyn_into::<Element>(), to.dyn_into::<Element>()) else {
+            continue;
+        };
+        let Ok(Some(computed)) = window().get_computed_style(&from) else {
+            continue;
+        };
+        let style = INLINED_PROPERTIES
+            .iter()
+            .filter_map(|name| {
+                let value = computed.get_property_value(name).ok()?;
+                (!value.is_empty()).then(|| format!("{}: {};", name, value))
+            })
+            .collect::<String>();
+        to.set_attribute("style", &style).map_err(dom_error)?;
+    }
+    Ok(())
+}
+
+/// A self-contained svg document of the `width` by `plot_height` plot `svg`
+/// as currently shown, on a solid background with `caption` underneath.
+/// Returns the markup and its total height.
+pub fn standalone_svg(
+    svg: &Element,
+    width: f64,
+    plot_height: f64,
+    caption: &Caption,
+    palette: &Palette,
+) -> Result<(String, f64), AppError> {
+    let dom_error = |_| AppError::DomError("Couldn't copy the plot".to_string());
+
+    let clone = svg
+        .clone_node_with_deep(true)
+        .map_err(dom_error)?
+        .dyn_into::<Element>()
+        .map_err(|_| AppError::DomError("Plot is not an element".to_string()))?;
+    inline_styles(svg, &clone)?;
+    for attr in ["x", "y"] {
+        clone.set_attribute(attr, "0").map_err(dom_error)?;
+    }
+    let plot = XmlSerializer::new()
+        .and_then(|s| s.serialize_to_string(&clone))
+        .map_err(dom_error)?;
+
+    let (caption, caption_height) = caption_markup(caption, width, palette);
+    let height = plot_height + caption_height;
+    let markup = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}"><rect width="{w}" height="{h}" fill="{bg}"/>{plot}<g transform="translate(0 {ph})">{caption}</g></svg>"#,
+        w = width,
+        h = height,
+        bg = palette.surface,
+        plot = plot,
+        ph = plot_height,
+        caption = caption,
+    );
+    Ok((markup, height))
+}
+
+fn blob_url(contents: &str, mime: &str) -> Result<String, AppError> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let blob = Blob::new_with_str_sequence_and_options(&parts, BlobPropertyBag::new().type_(mime))
+        .map_err(|_| AppError::DomError("Couldn't create blob".to_string()))?;
+    Url::create_object_url_with_blob(&blob)
+        .map_err(|_| AppError::DomError("Couldn't create object url".to_string()))
+}
+
+fn revoke_later(url: String) {
+    set_timeout(
+        move || {
+            let _ = Url::revoke_object_url(&url);
+        },
+        Duration::from_millis(REVOKE_DELAY_MS),
+    );
+}
+
+/// Start a download of `href` as `filename`
+pub fn download(href: &str, filename: &str) -> Result<(), AppError> {
+    let anchor = document()
+        .create_element("a")
+        .ok()
+        .and_then(|a| a.dyn_into::<HtmlAnchorElement>().ok())
+        .ok_or(AppError::DomError("Couldn't create download link".to_string()))?;
+    anchor.set_href(href);
+    anchor.set_download(filename);
+    anchor.click();
+    Ok(())
+}
+
+pub fn download_svg(markup: &str, filename: &str) -> Result<(), AppError> {
+    let url = blob_url(markup, "image/svg+xml;charset=utf-8")?;
+    let downloaded = download(&url, filename);
+    revoke_later(url);
+    downloaded
+}
+
+/// Rasterize svg `markup` of the given size through an offscreen canvas,
+/// `scale` times larger, and return a PNG data url.
+pub async fn svg_to_png(markup: &str, width: f64, height: f64, scale: f64) -> Result<String, AppError> {
+    let dom_error = |_| AppError::DomError("Couldn't rasterize the plot".to_string());
+    let url = blob_url(markup, "image/svg+xml;charset=utf-8")?;
+    let image = HtmlImageElement::new().map_err(dom_error)?;
+
+    let (tx, rx) = oneshot::channel::<bool>();
+    let tx = Rc::new(RefCell::new(Some(tx)));
+    let settle = |loaded: bool| {
+        let tx = tx.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            if let Some(tx) = tx.borrow_mut().take() {
+                let _ = tx.send(loaded);
+            }
+        })
+    };
+    let (on_load, on_error) = (settle(true), settle(false));
+    image.set_onload(Some(on_load.as_ref().unchecked_ref()));
+    image.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    image.set_src(&url);
+    let loaded = rx.await.unwrap_or(false);
+    revoke_later(url);
+    if !loaded {
+        return Err(AppError::DomError("Couldn't load the plot image".to_string()));
+    }
+
+    let canvas = document()
+        .create_element("canvas")
+        .map_err(dom_error)?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|_| AppError::DomError("Couldn't create canvas".to_string()))?;
+    canvas.set_width((width * scale).round() as u32);
+    canvas.set_height((height * scale).round() as u32);
+    let context = canvas
+        .get_context("2d")
+        .map_err(dom_error)?
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or(AppError::DomError("Couldn't get a 2d context".to_string()))?;
+    context
+        .draw_image_with_html_image_element_and_dw_and_dh(
+            &image,
+            0.0,
+            0.0,
+            width * scale,
+            height * scale,
+        )
+        .map_err(dom_error)?;
+    canvas.to_data_url_with_type("image/png").map_err(dom_error)
+}
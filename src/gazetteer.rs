@@ -0,0 +1,346 @@
+use std::{fmt, ops::Range, rc::Rc};
+
+use leptos::*;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    coords::{angular_separation, format_lat_lon, CoordinateNotation},
+    errors::AppError,
+    models::{SearchItem, SearchQueryParams, SearchResponse},
+};
+
+const GAZETTEER_URL: &str = "/gazetteer.tsv.z";
+const EARTH_RADIUS_KM: f64 = 6371.0;
+/// how close a place has to be to name the position after it, in km
+const SAME_PLACE_KM: f64 = 25.0;
+const NEARBY_KM: f64 = 150.0;
+/// matches on a later word of a name, eg "york" for "New York City", rank
+/// a little below matches on the first
+const LATER_WORD_PENALTY: f64 = 0.95;
+
+/// Lowercase `text` and strip accents, so that "zurich" finds "Zürich".
+pub fn normalize(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !('\u{300}'..='\u{36f}').contains(c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// edit distance counting a swap of neighbouring letters as one edit
+/// (optimal string alignment)
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// The best start, of the name or of a word in it, for `query` in `name`
+/// and how well it matches there
+fn best_match(query: &[char], name: &[char]) -> Option<(f64, usize)> {
+    if query.is_empty() {
+        return None;
+    }
+    (0..name.len())
+        .filter(|&i| i == 0 || !name[i - 1].is_alphanumeric())
+        .map(|start| {
+            let end = (start + query.len()).min(name.len());
+            let distance = edit_distance(query, &name[start..end]);
+            let score = 1.0 - distance as f64 / query.len() as f64;
+            if start == 0 {
+                (score, start)
+            } else {
+                (score * LATER_WORD_PENALTY, start)
+            }
+        })
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+}
+
+/// How well `query` matches the start of `name`, or the start of any word
+/// in it, from 0 to 1. Both should already be normalized. Only as much of
+/// the name as has been typed is compared, so a partly typed name scores 1.
+pub fn similarity(query: &str, name: &str) -> f64 {
+    let query: Vec<char> = query.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    best_match(&query, &name).map_or(0.0, |(score, _)| score.max(0.0))
+}
+
+/// The characters of `name`, as written, that `query` matched, for
+/// highlighting. Neither needs normalizing.
+pub fn match_range(query: &str, name: &str) -> Option<Range<usize>> {
+    let query: Vec<char> = normalize(query.trim()).chars().collect();
+    // normalized characters of the name, each with where it came from
+    let (normalized, origin): (Vec<char>, Vec<usize>) = name
+        .chars()
+        .enumerate()
+        .flat_map(|(i, c)| {
+            normalize(&c.to_string())
+                .chars()
+                .map(|n| (n, i))
+                .collect::<Vec<_>>()
+        })
+        .unzip();
+    let (score, start) = best_match(&query, &normalized)?;
+    if score <= 0.0 {
+        return None;
+    }
+    let end = (start + query.len()).min(normalized.len());
+    Some(origin[start]..origin[end - 1] + 1)
+}
+
+/// Where a position is, in words
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaceName {
+    pub item: SearchItem,
+    /// not in the place itself, but within driving distance of it
+    pub nearby: bool,
+}
+
+impl fmt::Display for PlaceName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nearby {
+            write!(f, "near ")?;
+        }
+        write!(f, "{}, {}", self.item.name, self.item.country)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Place {
+    item: SearchItem,
+    population: u64,
+    key: String,
+}
+
+/// Major cities for searching without the server.
+#[derive(Debug, Clone, Default)]
+pub struct Gazetteer {
+    places: Vec<Place>,
+}
+
+impl Gazetteer {
+    /// Parse rows of name, country, subdivision, latitude, longitude and
+    /// population, tab separated. Malformed rows are skipped.
+    pub fn parse(tsv: &str) -> Self {
+        let places = tsv
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let [name, country, sub_division, lat, lon, population] = fields[..] else {
+                    return None;
+                };
+                Some(Place {
+                    item: SearchItem {
+                        name: name.to_string(),
+                        country: country.to_string(),
+                        sub_division: sub_division.to_string(),
+                        lat: lat.parse().ok()?,
+                        lon: lon.parse().ok()?,
+                    },
+                    population: population.parse().unwrap_or(0),
+                    key: normalize(name),
+                })
+            })
+            .collect();
+        Self { places }
+    }
+
+    /// Search the way the server's `/search` does: places whose name is at
+    /// least `fuzzy` similar to `q`, best match first and then largest
+    /// first, at most `max_results` of them.
+    pub fn search(&self, query: &SearchQueryParams) -> SearchResponse {
+        let q = normalize(query.q.trim());
+        let mut matches: Vec<(f64, &Place)> = self
+            .places
+            .iter()
+            .map(|place| (similarity(&q, &place.key), place))
+            .filter(|(score, _)| *score > 0.0 && *score >= query.fuzzy)
+            .collect();
+        matches.sort_by(|(a, pa), (b, pb)| {
+            b.total_cmp(a).then(pb.population.cmp(&pa.population))
+        });
+        SearchResponse {
+            items: matches
+                .into_iter()
+                .take(query.max_results.max(0) as usize)
+                .map(|(_, place)| place.item.clone())
+                .collect(),
+        }
+    }
+
+    /// Name the position at `lat`, `lon` (degrees) after the closest place,
+    /// preferring the largest of those close enough to count as the same.
+    pub fn reverse(&self, lat: f64, lon: f64) -> Option<PlaceName> {
+        let distance = |place: &Place| {
+            EARTH_RADIUS_KM
+                * angular_separation(
+                    lon.to_radians(),
+                    lat.to_radians(),
+                    place.item.lon.to_radians(),
+                    place.item.lat.to_radians(),
+                )
+        };
+        let with_distance = self.places.iter().map(|place| (distance(place), place));
+        if let Some((_, place)) = with_distance
+            .clone()
+            .filter(|(km, _)| *km < SAME_PLACE_KM)
+            .max_by_key(|(_, place)| place.population)
+        {
+            return Some(PlaceName { item: place.item.clone(), nearby: false });
+        }
+        with_distance
+            .filter(|(km, _)| *km < NEARBY_KM)
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, place)| PlaceName { item: place.item.clone(), nearby: true })
+    }
+}
+
+async fn fetch_gazetteer() -> Result<Gazetteer, AppError> {
+    let url = format!("{}{}", window().location().origin().unwrap_or_default(), GAZETTEER_URL);
+    let compressed = reqwest::get(url)
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| AppError::FetchError(e.to_string()))?
+        .bytes()
+        .await
+        .map_err(|e| AppError::FetchError(e.to_string()))?;
+    let tsv = miniz_oxide::inflate::decompress_to_vec_zlib(&compressed)
+        .map_err(|e| AppError::FetchError(format!("Couldn't decompress gazetteer: {:?}", e)))?;
+    Ok(Gazetteer::parse(&String::from_utf8_lossy(&tsv)))
+}
+
+/// The bundled gazetteer, fetched the first time `load` is called. It only
+/// holds major cities unless built from GeoNames, see the README.
+#[derive(Debug, Clone, Copy)]
+pub struct GazetteerContext {
+    pub gazetteer: RwSignal<Option<Rc<Gazetteer>>>,
+    loading: RwSignal<bool>,
+}
+
+impl GazetteerContext {
+    pub fn load(&self) {
+        if self.loading.get_untracked() || self.gazetteer.with_untracked(Option::is_some) {
+            return;
+        }
+        self.loading.set(true);
+        let (gazetteer, loading) = (self.gazetteer, self.loading);
+        spawn_local(async move {
+            match fetch_gazetteer().await {
+                Ok(loaded) => gazetteer.set(Some(Rc::new(loaded))),
+                Err(e) => logging::warn!("gazetteer unavailable: {}", e),
+            }
+            loading.set(false);
+        });
+    }
+
+    /// the place at `lat`, `lon`, once the gazetteer has loaded
+    pub fn reverse(&self, lat: f64, lon: f64) -> Option<PlaceName> {
+        self.gazetteer.with(|g| g.as_ref()?.reverse(lat, lon))
+    }
+
+    /// the place name, if known, and coordinates of `lat`, `lon`, for
+    /// captions
+    pub fn describe(&self, lat: f64, lon: f64) -> String {
+        let coordinates = format_lat_lon(lat, lon, CoordinateNotation::Decimal);
+        match self.reverse(lat, lon) {
+            Some(place) => format!("{} ({})", place, coordinates),
+            None => coordinates,
+        }
+    }
+
+    /// local results for `query`, if the gazetteer has loaded
+    pub fn search(&self, query: &SearchQueryParams) -> Option<SearchResponse> {
+        self.gazetteer.with(|g| g.as_ref().map(|g| g.search(query)))
+    }
+}
+
+pub fn use_gazetteer() -> GazetteerContext {
+    use_context::<GazetteerContext>()
+        .expect("GazetteerContext missing from context, provide_gazetteer() must run in an ancestor")
+}
+
+pub fn provide_gazetteer() {
+    provide_context(GazetteerContext {
+        gazetteer: create_rw_signal(None),
+        loading: create_rw_signal(false),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLACES: &str = "New York City\tUnited States\tNew York\t40.7143\t-74.0060\t8804190
+Zürich\tSwitzerland\tZurich\t47.3667\t8.5500\t341730
+York\tUnited Kingdom\tEngland\t53.9576\t-1.0827\t153717
+Yorkton\tCanada\tSaskatchewan\t51.2114\t-102.4628\t16343
+";
+
+    fn search(q: &str, fuzzy: f64, max_results: i64) -> Vec<String> {
+        let query = SearchQueryParams { q: q.to_string(), max_results, fuzzy };
+        Gazetteer::parse(PLACES)
+            .search(&query)
+            .items
+            .into_iter()
+            .map(|item| item.name)
+            .collect()
+    }
+
+    #[test]
+    fn similarity_scores_typed_prefixes_and_typos() {
+        assert_eq!(similarity("zur", "zurich"), 1.0);
+        assert_eq!(similarity("zurich", "zurich"), 1.0);
+        // a swap of neighbouring letters is one edit
+        assert!((similarity("zurcih", "zurich") - 5.0 / 6.0).abs() < 1e-12);
+        assert_eq!(similarity("xyz", "zurich"), 0.0);
+        assert_eq!(similarity("", "zurich"), 0.0);
+    }
+
+    #[test]
+    fn similarity_ranks_later_words_below_the_first() {
+        assert_eq!(similarity("york", "new york city"), LATER_WORD_PENALTY);
+        assert!(similarity("york", "york") > similarity("york", "new york city"));
+    }
+
+    #[test]
+    fn match_range_covers_accented_letters_as_written() {
+        assert_eq!(match_range("zurich", "Zürich"), Some(0..6));
+        assert_eq!(match_range("ZÜR", "Zürich"), Some(0..3));
+        assert_eq!(match_range("paulo", "São Paulo"), Some(4..9));
+    }
+
+    #[test]
+    fn match_range_finds_later_words() {
+        assert_eq!(match_range("york", "New York City"), Some(4..8));
+        assert_eq!(match_range("cit", "New York City"), Some(9..12));
+        assert_eq!(match_range("xyz", "Zürich"), None);
+    }
+
+    #[test]
+    fn search_ranks_best_match_then_largest() {
+        assert_eq!(search("york", 0.5, 10), ["York", "Yorkton", "New York City"]);
+        assert_eq!(search("york", 0.5, 1), ["York"]);
+        assert_eq!(search("Zurich", 0.5, 10), ["Zürich"]);
+    }
+
+    #[test]
+    fn search_drops_matches_below_the_threshold() {
+        assert_eq!(search("york", 0.97, 10), ["York", "Yorkton"]);
+        assert_eq!(search("zurcih", 0.8, 10), ["Zürich"]);
+        assert!(search("zurcih", 0.9, 10).is_empty());
+        assert!(search("", 0.0, 10).is_empty());
+    }
+}
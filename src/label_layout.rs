@@ -0,0 +1,354 @@
+use std::f64::consts::FRAC_PI_2;
+
+use crate::projection::SkyTransform;
+
+/// space kept between a label and whatever it labels or avoids, in pixels
+pub const LABEL_GAP: f64 = 3.0;
+/// how far a label may be pushed along its ray to get clear
+const MAX_NUDGES: usize = 12;
+const NUDGE: f64 = 2.0;
+
+/// A plotted object that labels should keep clear of.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marker {
+    pub x: f64,
+    pub y: f64,
+    pub r: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelKind {
+    /// elevation of a ring
+    Ring,
+    /// azimuth of a spoke, outside the plot
+    Spoke,
+    /// N, E, S or W just inside the horizon
+    Cardinal,
+}
+
+/// A label centred on (`x`, `y`) with a bounding box of `width` by `height`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub kind: LabelKind,
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Label {
+    pub fn overlaps_marker(&self, marker: &Marker) -> bool {
+        let dx = ((marker.x - self.x).abs() - self.width / 2.0).max(0.0);
+        let dy = ((marker.y - self.y).abs() - self.height / 2.0).max(0.0);
+        dx * dx + dy * dy < (marker.r + LABEL_GAP).powi(2)
+    }
+
+    pub fn overlaps(&self, other: &Label) -> bool {
+        (self.x - other.x).abs() * 2.0 < self.width + other.width
+            && (self.y - other.y).abs() * 2.0 < self.height + other.height
+    }
+
+    fn is_clear(&self, markers: &[Marker], placed: &[Label]) -> bool {
+        !markers.iter().any(|m| self.overlaps_marker(m)) && !placed.iter().any(|l| self.overlaps(l))
+    }
+}
+
+/// rough width of serif text, for when real text metrics aren't available
+pub fn estimate_text_width(text: &str, font_size: f64) -> f64 {
+    0.55 * font_size * text.chars().count() as f64
+}
+
+/// Places the ring, spoke and cardinal labels of a polar plot. Text widths
+/// come from `measure`, so layouts are deterministic for a given measure.
+pub struct LabelLayout<'a> {
+    pub sky: SkyTransform,
+    pub font_size: f64,
+    pub measure: &'a dyn Fn(&str) -> f64,
+}
+
+impl<'a> LabelLayout<'a> {
+    fn label(&self, kind: LabelKind, text: String, x: f64, y: f64) -> Label {
+        Label {
+            kind,
+            width: (self.measure)(&text),
+            height: self.font_size,
+            text,
+            x,
+            y,
+        }
+    }
+
+    /// unit vector pointing towards azimuth `az` (radians) on screen
+    fn direction(az: f64) -> (f64, f64) {
+        ((az - FRAC_PI_2).cos(), (az - FRAC_PI_2).sin())
+    }
+
+    /// A label along azimuth `az` whose box just touches the circle of
+    /// radius `r`, outside it if `outside` and inside otherwise.
+    fn along(&self, kind: LabelKind, text: String, az: f64, r: f64, outside: bool) -> Label {
+        let (dx, dy) = Self::direction(az);
+        let mut label = self.label(kind, text, 0.0, 0.0);
+        let extent = LABEL_GAP + (dx.abs() * label.width + dy.abs() * label.height) / 2.0;
+        let offset = if outside { r + extent } else { r - extent };
+        label.x = self.sky.center_x + offset * dx;
+        label.y = self.sky.center_y + offset * dy;
+        label
+    }
+
+    /// A label along azimuth `az` touching the circle of radius `r`, outside
+    /// it for spokes, pushed away from it until it is clear of `markers` and
+    /// `placed`.
+    fn nudged(
+        &self,
+        kind: LabelKind,
+        text: &str,
+        az: f64,
+        r: f64,
+        markers: &[Marker],
+        placed: &[Label],
+    ) -> Label {
+        let outside = kind == LabelKind::Spoke;
+        let step = if outside { NUDGE } else { -NUDGE };
+        let mut label = self.along(kind, text.to_string(), az, r, outside);
+        for i in 1..=MAX_NUDGES {
+            if label.is_clear(markers, placed) {
+                break;
+            }
+            label = self.along(kind, text.to_string(), az, r + step * i as f64, outside);
+        }
+        label
+    }
+
+    /// degree labels just outside the plot at each spoke azimuth (degrees),
+    /// moved outwards past markers on the horizon
+    pub fn spoke_labels(&self, azimuths: &[f64], markers: &[Marker]) -> Vec<Label> {
+        azimuths
+            .iter()
+            .map(|az| {
+                let text = format!("{:.0}°", az);
+                self.nudged(
+                    LabelKind::Spoke,
+                    &text,
+                    az.to_radians(),
+                    self.sky.radius,
+                    markers,
+                    &[],
+                )
+            })
+            .collect()
+    }
+
+    /// N, E, S and W just inside the horizon, moved inwards past any markers
+    pub fn cardinal_labels(&self, markers: &[Marker]) -> Vec<Label> {
+        let horizon = self.sky.horizon_radius();
+        let mut placed: Vec<Label> = Vec::new();
+        for (az, text) in [(0.0, "N"), (90.0, "E"), (180.0, "S"), (270.0, "W")] {
+            let label = self.nudged(
+                LabelKind::Cardinal,
+                text,
+                f64::to_radians(az),
+                horizon,
+                markers,
+                &placed,
+            );
+            placed.push(label);
+        }
+        placed
+    }
+
+    /// Elevation labels centred on their rings. All rings are labelled along
+    /// the same azimuth where possible, trying the gaps between `spokes`
+    /// in turn until no label hits a marker or a label in `avoid`.
+    pub fn ring_labels(
+        &self,
+        elevations: &[f64],
+        spokes: &[f64],
+        markers: &[Marker],
+        avoid: &[Label],
+    ) -> Vec<Label> {
+        let ring = |el: f64, az: f64| {
+            let (dx, dy) = Self::direction(az.to_radians());
+            let r = self.sky.el_radius(el.to_radians());
+            self.label(
+                LabelKind::Ring,
+                format!("{:.0}°", el),
+                self.sky.center_x + r * dx,
+                self.sky.center_y + r * dy,
+            )
+        };
+        let candidates: Vec<f64> = spokes
+            .iter()
+            .zip(spokes.iter().cycle().skip(1))
+            .map(|(a, b)| (a + (b - a).rem_euclid(360.0) / 2.0).rem_euclid(360.0))
+            .collect();
+        let fallback = candidates.first().copied().unwrap_or(15.0);
+
+        let all_clear = |az: f64| {
+            let mut placed = avoid.to_vec();
+            for el in elevations {
+                let label = ring(*el, az);
+                if !label.is_clear(markers, &placed) {
+                    return None;
+                }
+                placed.push(label);
+            }
+            Some(placed.split_off(avoid.len()))
+        };
+        if let Some(labels) = candidates.iter().find_map(|az| all_clear(*az)) {
+            return labels;
+        }
+
+        // no shared azimuth works, so place each label on its own
+        let mut placed = avoid.to_vec();
+        for el in elevations {
+            let label = candidates
+                .iter()
+                .map(|az| ring(*el, *az))
+                .find(|label| label.is_clear(markers, &placed))
+                .unwrap_or_else(|| ring(*el, fallback));
+            placed.push(label);
+        }
+        placed.split_off(avoid.len())
+    }
+
+    pub fn layout(&self, elevations: &[f64], spokes: &[f64], markers: &[Marker]) -> Vec<Label> {
+        let mut labels = self.spoke_labels(spokes, markers);
+        labels.extend(self.cardinal_labels(markers));
+        let rings = self.ring_labels(elevations, spokes, markers, &labels);
+        labels.extend(rings);
+        labels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projection::ProjectionKind;
+
+    const WIDTHS: [f64; 4] = [300.0, 450.0, 600.0, 900.0];
+    const FONT_SIZES: [f64; 3] = [8.0, 10.0, 12.0];
+
+    fn elevations() -> Vec<f64> {
+        (0..6).map(|i| (i * 15) as f64).collect()
+    }
+
+    fn spokes() -> Vec<f64> {
+        (0..12).map(|i| (i * 30) as f64).collect()
+    }
+
+    /// sized and centred the way `PolarPlot` does it
+    fn sky(width: f64) -> SkyTransform {
+        let radius = (2.0 * width / 5.0).floor();
+        let padding = width / 2.0 - radius;
+        SkyTransform {
+            projection: ProjectionKind::default(),
+            radius,
+            center_x: radius + padding,
+            center_y: radius + padding / 2.0,
+            below_horizon: false,
+        }
+    }
+
+    /// objects near the horizon under N and E, and on the first ring gaps
+    fn markers(sky: &SkyTransform) -> Vec<Marker> {
+        [
+            (0.0, 3.0),
+            (90.0, 1.0),
+            (15.0, 15.0),
+            (45.0, 30.0),
+            (200.0, 60.0),
+        ]
+        .into_iter()
+        .map(|(az, el): (f64, f64)| {
+            let (x, y) = sky.az_el(az.to_radians(), el.to_radians());
+            Marker { x, y, r: 4.0 }
+        })
+        .collect()
+    }
+
+    fn layout(width: f64, font_size: f64) -> Vec<Label> {
+        let sky = sky(width);
+        let measure = move |text: &str| estimate_text_width(text, font_size);
+        LabelLayout {
+            sky,
+            font_size,
+            measure: &measure,
+        }
+        .layout(&elevations(), &spokes(), &markers(&sky))
+    }
+
+    #[test]
+    fn labels_keep_clear_of_markers_and_each_other() {
+        for width in WIDTHS {
+            for font_size in FONT_SIZES {
+                let labels = layout(width, font_size);
+                let markers = markers(&sky(width));
+                for (i, label) in labels.iter().enumerate() {
+                    for marker in &markers {
+                        assert!(
+                            !label.overlaps_marker(marker),
+                            "{:?} hits {:?} at {}px, {}pt",
+                            label,
+                            marker,
+                            width,
+                            font_size
+                        );
+                    }
+                    for other in &labels[i + 1..] {
+                        assert!(
+                            !label.overlaps(other),
+                            "{:?} hits {:?} at {}px, {}pt",
+                            label,
+                            other,
+                            width,
+                            font_size
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_label_is_placed() {
+        for width in WIDTHS {
+            for font_size in FONT_SIZES {
+                let labels = layout(width, font_size);
+                let texts = |kind: LabelKind| {
+                    labels
+                        .iter()
+                        .filter(|l| l.kind == kind)
+                        .map(|l| l.text.as_str())
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(texts(LabelKind::Cardinal), ["N", "E", "S", "W"]);
+                assert_eq!(texts(LabelKind::Spoke).len(), spokes().len());
+                assert_eq!(texts(LabelKind::Ring).len(), elevations().len());
+            }
+        }
+    }
+
+    #[test]
+    fn cardinals_stay_inside_the_horizon() {
+        for width in WIDTHS {
+            let sky = sky(width);
+            for label in layout(width, 10.0)
+                .iter()
+                .filter(|l| l.kind == LabelKind::Cardinal)
+            {
+                let r = (label.x - sky.center_x).hypot(label.y - sky.center_y);
+                assert!(r < sky.horizon_radius(), "{:?} at {}px", label, width);
+            }
+        }
+    }
+
+    #[test]
+    fn layout_is_deterministic() {
+        for width in WIDTHS {
+            for font_size in FONT_SIZES {
+                assert_eq!(layout(width, font_size), layout(width, font_size));
+            }
+        }
+    }
+}
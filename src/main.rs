@@ -4,6 +4,26 @@ mod api;
 mod errors;
 mod components;
 mod utils;
+mod coords;
+mod constellations;
+mod projection;
+mod orientation;
+mod theme;
+mod label_layout;
+mod export;
+mod almanac;
+mod recommend;
+mod alerts;
+mod service_worker;
+mod gazetteer;
+mod satellites;
+mod minor_bodies;
+mod custom_targets;
+mod clock;
+mod mock;
+mod state;
+#[cfg(all(test, target_arch = "wasm32"))]
+mod browser_tests;
 
 use app::*;
 use leptos::*;
@@ -0,0 +1,560 @@
+use std::f64::consts::TAU;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use leptos::*;
+use leptos_use::storage::{use_local_storage, JsonCodec};
+
+use crate::{
+    almanac::crossing,
+    coords::{
+        ecliptic_to_equatorial, equatorial_to_horizontal, julian_centuries, julian_date,
+        local_sidereal_time, mean_obliquity, sun_ecliptic,
+    },
+    errors::AppError,
+    models::{Position, SkyTarget, TargetId, TargetKind},
+    state::use_app_state,
+    AstronObjectsRw,
+};
+
+const MINOR_BODIES_STORAGE_KEY: &str = "planet-tracker-minor-bodies";
+
+/// Gaussian gravitational constant, radians a day
+const GAUSS_K: f64 = 0.01720209895;
+/// light travel time for 1 AU, in days
+const LIGHT_TIME_DAYS_PER_AU: f64 = 0.0057755183;
+/// general precession in ecliptic longitude, radians a Julian century
+const PRECESSION_PER_CENTURY: f64 = 1.396971 * std::f64::consts::PI / 180.0;
+/// how far ahead rising and setting are looked for
+const RISE_SET_WINDOW_HOURS: i64 = 24;
+
+/// How bright a body is, from its absolute magnitude `h`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MagnitudeModel {
+    /// asteroids, with the IAU H, G phase function
+    Asteroid { h: f64, g: f64 },
+    /// comets, m = H + 5 log Δ + 2.5 K log r
+    Comet { h: f64, k: f64 },
+}
+
+/// The shape and orientation of an orbit. Angles are in radians, referred
+/// to the ecliptic and equinox of J2000.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Orbit {
+    /// perihelion distance, AU
+    pub q: f64,
+    pub e: f64,
+    pub i: f64,
+    /// longitude of the ascending node
+    pub node: f64,
+    /// argument of perihelion
+    pub peri: f64,
+    /// Julian date of perihelion passage
+    pub perihelion_jd: f64,
+}
+
+/// A comet or asteroid from MPC orbital elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinorBody {
+    pub name: String,
+    pub orbit: Orbit,
+    pub magnitude: MagnitudeModel,
+}
+
+/// `line`'s characters in columns `start` to `end`, counting from 1 as the
+/// MPC formats do
+fn columns(line: &str, start: usize, end: usize) -> &str {
+    let from = line
+        .char_indices()
+        .nth(start - 1)
+        .map_or(line.len(), |(i, _)| i);
+    let to = line.char_indices().nth(end).map_or(line.len(), |(i, _)| i);
+    line.get(from..to).unwrap_or("").trim()
+}
+
+fn number(line: &str, start: usize, end: usize, what: &str) -> Result<f64, AppError> {
+    columns(line, start, end).parse().map_err(|_| {
+        AppError::ParseError(format!("Couldn't read the {} in \"{}\"", what, line.trim()))
+    })
+}
+
+/// a digit or letter of an MPC packed date: 1-9, then A for 10 onwards
+fn packed_digit(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        'A'..='V' => Some(c as u32 - 'A' as u32 + 10),
+        _ => None,
+    }
+}
+
+/// Julian date of an MPC packed date, eg `K2555` for 2025 May 5
+fn packed_date_jd(packed: &str) -> Option<f64> {
+    let chars: Vec<char> = packed.chars().collect();
+    let [century, y1, y2, month, day] = chars[..] else {
+        return None;
+    };
+    let year =
+        packed_digit(century)? as i32 * 100 + (packed_digit(y1)? * 10 + packed_digit(y2)?) as i32;
+    let date = NaiveDate::from_ymd_opt(year, packed_digit(month)?, packed_digit(day)?)?;
+    Some(julian_date(&date.and_hms_opt(0, 0, 0)?))
+}
+
+impl MinorBody {
+    /// Read one line in either the MPC's comet format, as in CometEls.txt,
+    /// or its asteroid format, as in MPCORB.DAT.
+    pub fn parse(line: &str) -> Result<Self, AppError> {
+        let is_comet = matches!(columns(line, 5, 5), "C" | "P" | "D" | "X" | "I" | "A")
+            && columns(line, 15, 18).parse::<i32>().is_ok();
+        if is_comet {
+            Self::parse_comet(line)
+        } else {
+            Self::parse_asteroid(line)
+        }
+    }
+
+    fn parse_comet(line: &str) -> Result<Self, AppError> {
+        let year = number(line, 15, 18, "perihelion year")? as i32;
+        let month = number(line, 20, 21, "perihelion month")? as u32;
+        let day = number(line, 23, 29, "perihelion day")?;
+        let perihelion = NaiveDate::from_ymd_opt(year, month, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .ok_or_else(|| {
+                AppError::ParseError(format!("Invalid perihelion date in \"{}\"", line.trim()))
+            })?;
+        let name = match columns(line, 103, 158) {
+            "" => columns(line, 1, 12).to_string(),
+            name => name.to_string(),
+        };
+        Ok(Self {
+            name,
+            orbit: Orbit {
+                q: number(line, 31, 39, "perihelion distance")?,
+                e: number(line, 42, 49, "eccentricity")?,
+                peri: number(line, 52, 59, "argument of perihelion")?.to_radians(),
+                node: number(line, 62, 69, "ascending node")?.to_radians(),
+                i: number(line, 72, 79, "inclination")?.to_radians(),
+                perihelion_jd: julian_date(&perihelion) + day - 1.0,
+            },
+            magnitude: MagnitudeModel::Comet {
+                h: number(line, 92, 95, "absolute magnitude")?,
+                k: number(line, 97, 100, "slope parameter")?,
+            },
+        })
+    }
+
+    fn parse_asteroid(line: &str) -> Result<Self, AppError> {
+        let epoch = packed_date_jd(columns(line, 21, 25)).ok_or_else(|| {
+            AppError::ParseError(format!("Couldn't read the epoch in \"{}\"", line.trim()))
+        })?;
+        let mean_anomaly = number(line, 27, 35, "mean anomaly")?;
+        let e = number(line, 71, 79, "eccentricity")?;
+        let a = number(line, 93, 103, "semimajor axis")?;
+        let daily_motion = number(line, 81, 91, "mean daily motion")?;
+        let name = match columns(line, 167, 194) {
+            "" => columns(line, 1, 7).to_string(),
+            name => name.to_string(),
+        };
+        Ok(Self {
+            name,
+            orbit: Orbit {
+                q: a * (1.0 - e),
+                e,
+                peri: number(line, 38, 46, "argument of perihelion")?.to_radians(),
+                node: number(line, 49, 57, "ascending node")?.to_radians(),
+                i: number(line, 60, 68, "inclination")?.to_radians(),
+                // back from the epoch to the last perihelion
+                perihelion_jd: epoch - mean_anomaly / daily_motion,
+            },
+            magnitude: MagnitudeModel::Asteroid {
+                h: number(line, 9, 13, "absolute magnitude")?,
+                g: columns(line, 15, 19).parse().unwrap_or(0.15),
+            },
+        })
+    }
+}
+
+impl Orbit {
+    /// True anomaly and distance from the Sun in AU at Julian date `jd`, by
+    /// two-body motion
+    fn anomaly_distance(&self, jd: f64) -> (f64, f64) {
+        let (q, e) = (self.q, self.e);
+        let t = jd - self.perihelion_jd;
+        if (e - 1.0).abs() < 1e-6 {
+            // parabolic, by Barker's equation
+            let w = 3.0 * GAUSS_K / (2.0 * q * q * q).sqrt() * t;
+            let y = (w / 2.0 + (w * w / 4.0 + 1.0).sqrt()).cbrt();
+            let s = y - 1.0 / y;
+            (2.0 * s.atan(), q * (1.0 + s * s))
+        } else if e < 1.0 {
+            let a = q / (1.0 - e);
+            let mean_anomaly = (GAUSS_K * t / a.powf(1.5)).rem_euclid(TAU);
+            let mut ecc = if e > 0.8 {
+                std::f64::consts::PI
+            } else {
+                mean_anomaly
+            };
+            for _ in 0..50 {
+                let step = (ecc - e * ecc.sin() - mean_anomaly) / (1.0 - e * ecc.cos());
+                ecc -= step;
+                if step.abs() < 1e-12 {
+                    break;
+                }
+            }
+            let nu = 2.0
+                * ((1.0 + e).sqrt() * (ecc / 2.0).sin())
+                    .atan2((1.0 - e).sqrt() * (ecc / 2.0).cos());
+            (nu, a * (1.0 - e * ecc.cos()))
+        } else {
+            let a = q / (e - 1.0);
+            let mean_anomaly = GAUSS_K * t / a.powf(1.5);
+            let mut hyp = (2.0 * mean_anomaly / e).asinh();
+            for _ in 0..50 {
+                let step = (e * hyp.sinh() - hyp - mean_anomaly) / (e * hyp.cosh() - 1.0);
+                hyp -= step;
+                if step.abs() < 1e-12 {
+                    break;
+                }
+            }
+            let nu = 2.0 * (((e + 1.0) / (e - 1.0)).sqrt() * (hyp / 2.0).tanh()).atan();
+            (nu, a * (e * hyp.cosh() - 1.0))
+        }
+    }
+
+    /// heliocentric ecliptic position in AU at Julian date `jd`
+    fn heliocentric(&self, jd: f64) -> [f64; 3] {
+        let (nu, r) = self.anomaly_distance(jd);
+        let u = self.peri + nu;
+        let (sin_node, cos_node) = self.node.sin_cos();
+        [
+            r * (cos_node * u.cos() - sin_node * u.sin() * self.i.cos()),
+            r * (sin_node * u.cos() + cos_node * u.sin() * self.i.cos()),
+            r * u.sin() * self.i.sin(),
+        ]
+    }
+}
+
+/// Where a minor body is at one moment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ephemeris {
+    /// right ascension and declination of date, radians
+    pub ra: f64,
+    pub dec: f64,
+    /// distances from the Sun and the Earth, AU
+    pub r: f64,
+    pub delta: f64,
+    pub magnitude: f64,
+}
+
+impl MinorBody {
+    /// Geocentric position and brightness at `when` (UTC), corrected for
+    /// light time. Good to a minute of arc or so, depending on the elements.
+    pub fn ephemeris(&self, when: &NaiveDateTime) -> Ephemeris {
+        let jd = julian_date(when);
+        // the Earth is opposite the Sun, and the Sun's longitude is of date
+        let (sun_lon, sun_distance) = sun_ecliptic(jd);
+        let sun_lon = sun_lon - PRECESSION_PER_CENTURY * julian_centuries(jd);
+        let earth = [
+            -sun_distance * sun_lon.cos(),
+            -sun_distance * sun_lon.sin(),
+            0.0,
+        ];
+
+        let geocentric =
+            |body: [f64; 3]| [body[0] - earth[0], body[1] - earth[1], body[2] - earth[2]];
+        let length = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        let delta = length(geocentric(self.orbit.heliocentric(jd)));
+        let body = self.orbit.heliocentric(jd - LIGHT_TIME_DAYS_PER_AU * delta);
+        let (r, v) = (length(body), geocentric(body));
+        let delta = length(v);
+
+        let lon = v[1].atan2(v[0]) + PRECESSION_PER_CENTURY * julian_centuries(jd);
+        let lat = (v[2] / delta).asin();
+        // the ecliptic itself moves too, but by well under the accuracy of
+        // the elements
+        let (ra, dec) = ecliptic_to_equatorial(lon, lat, mean_obliquity(jd));
+
+        let magnitude = match self.magnitude {
+            MagnitudeModel::Asteroid { h, g } => {
+                let phase = ((r * r + delta * delta - sun_distance * sun_distance)
+                    / (2.0 * r * delta))
+                    .clamp(-1.0, 1.0)
+                    .acos();
+                let tan_half = (phase / 2.0).tan();
+                let phi1 = (-3.33 * tan_half.powf(0.63)).exp();
+                let phi2 = (-1.87 * tan_half.powf(1.22)).exp();
+                h + 5.0 * (r * delta).log10() - 2.5 * ((1.0 - g) * phi1 + g * phi2).log10()
+            }
+            MagnitudeModel::Comet { h, k } => h + 5.0 * delta.log10() + 2.5 * k * r.log10(),
+        };
+
+        Ephemeris {
+            ra,
+            dec,
+            r,
+            delta,
+            magnitude,
+        }
+    }
+
+    fn horizontal(&self, when: &NaiveDateTime, position: &Position) -> (f64, f64) {
+        let Ephemeris { ra, dec, .. } = self.ephemeris(when);
+        let lst = local_sidereal_time(julian_date(when), position.lon.to_radians());
+        equatorial_to_horizontal(ra, dec, position.lat.to_radians(), lst)
+    }
+
+    pub fn kind(&self) -> TargetKind {
+        match self.magnitude {
+            MagnitudeModel::Asteroid { .. } => TargetKind::Asteroid,
+            MagnitudeModel::Comet { .. } => TargetKind::Comet,
+        }
+    }
+
+    /// The body as a target listed and plotted alongside the planets
+    pub fn target(&self, position: &Position, when: NaiveDateTime) -> SkyTarget {
+        let ephemeris = self.ephemeris(&when);
+        let (az, el) = self.horizontal(&when, position);
+        let end = when + Duration::hours(RISE_SET_WINDOW_HOURS);
+        let altitude = |t: &NaiveDateTime| self.horizontal(t, position).1.to_degrees();
+        SkyTarget {
+            id: TargetId::new(self.kind(), &self.name),
+            kind: self.kind(),
+            name: self.name.clone(),
+            color: None,
+            body: None,
+            magnitude: ephemeris.magnitude,
+            size: 0.0,
+            az,
+            el,
+            ra: ephemeris.ra,
+            dec: ephemeris.dec,
+            setting_time: crossing(altitude, when, end, 0.0, true),
+            rising_time: crossing(altitude, when, end, 0.0, false),
+            transit_time: None,
+            when,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MinorBodies {
+    /// MPC element lines, one per body
+    pub elements: Signal<Vec<String>>,
+    pub set_elements: WriteSignal<Vec<String>>,
+    pub error: RwSignal<Option<AppError>>,
+}
+
+impl MinorBodies {
+    /// Add every line of pasted `text` that reads as MPC elements, replacing
+    /// any body of the same name.
+    pub fn add(&self, text: &str) {
+        let mut parsed = Vec::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            match MinorBody::parse(line) {
+                Ok(body) => parsed.push((body.name, line.to_string())),
+                Err(e) => return self.error.set(Some(e)),
+            }
+        }
+        self.error.set(None);
+        self.set_elements.update(|elements| {
+            elements.retain(|line| {
+                MinorBody::parse(line).map_or(true, |body| {
+                    parsed.iter().all(|(name, _)| *name != body.name)
+                })
+            });
+            elements.extend(parsed.into_iter().map(|(_, line)| line));
+        });
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.set_elements.update(|elements| {
+            elements.retain(|line| MinorBody::parse(line).map_or(true, |body| body.name != name))
+        });
+    }
+}
+
+/// Keep minor body elements in local storage and their positions in
+/// `objs`, next to the server's planets.
+pub fn use_minor_bodies(objs: AstronObjectsRw) -> MinorBodies {
+    let position_time = use_app_state().position_time;
+    let (elements, set_elements, _) =
+        use_local_storage::<Vec<String>, JsonCodec>(MINOR_BODIES_STORAGE_KEY);
+
+    create_effect(move |_| {
+        let (position, when) = position_time.get();
+        let targets: Vec<SkyTarget> = elements.with(|elements| {
+            elements
+                .iter()
+                .filter_map(|line| MinorBody::parse(line).ok())
+                .map(|body| body.target(&position, when.naive_utc()))
+                .collect()
+        });
+        objs.update(|objs| {
+            objs.retain(|obj| !matches!(obj.kind, TargetKind::Comet | TargetKind::Asteroid));
+            objs.extend(targets);
+        });
+    });
+
+    MinorBodies {
+        elements,
+        set_elements,
+        error: create_rw_signal(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::{precess, J2000};
+
+    /// Encke's elements from Meeus, Astronomical Algorithms, example 33.a,
+    /// in the MPC's comet format
+    const ENCKE: &str = "0002P         1990 10 28.5450 0.330886   0.850220  186.2335  334.7501   11.9452  19901006  11.5 10.0  2P/Encke";
+    const CERES: &str = "00001    3.33  0.15 K2555 188.70269   73.27343   80.25221   10.58780  0.0794013  0.21424651   2.7660512                                                               (1) Ceres";
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() < tolerance
+    }
+
+    fn at(t: f64, q: f64, e: f64) -> (f64, f64) {
+        let orbit = Orbit {
+            q,
+            e,
+            i: 0.0,
+            node: 0.0,
+            peri: 0.0,
+            perihelion_jd: J2000,
+        };
+        orbit.anomaly_distance(J2000 + t)
+    }
+
+    #[test]
+    fn parses_comet_lines() {
+        let encke = MinorBody::parse(ENCKE).unwrap();
+        assert_eq!(encke.name, "2P/Encke");
+        assert_eq!(encke.magnitude, MagnitudeModel::Comet { h: 11.5, k: 10.0 });
+        assert_eq!(encke.kind(), TargetKind::Comet);
+        let orbit = encke.orbit;
+        assert_eq!((orbit.q, orbit.e), (0.330886, 0.85022));
+        assert!(close(orbit.peri.to_degrees(), 186.2335, 1e-9));
+        assert!(close(orbit.node.to_degrees(), 334.7501, 1e-9));
+        assert!(close(orbit.i.to_degrees(), 11.9452, 1e-9));
+        // 1990 October 28.5450
+        assert!(close(orbit.perihelion_jd, 2448193.045, 1e-6));
+    }
+
+    #[test]
+    fn parses_asteroid_lines() {
+        let ceres = MinorBody::parse(CERES).unwrap();
+        assert_eq!(ceres.name, "(1) Ceres");
+        assert_eq!(
+            ceres.magnitude,
+            MagnitudeModel::Asteroid { h: 3.33, g: 0.15 }
+        );
+        assert_eq!(ceres.kind(), TargetKind::Asteroid);
+        let orbit = ceres.orbit;
+        assert!(close(orbit.q, 2.7660512 * (1.0 - 0.0794013), 1e-12));
+        assert!(close(orbit.i.to_degrees(), 10.5878, 1e-9));
+        // epoch K2555, 2025 May 5, less the time since perihelion
+        assert_eq!(packed_date_jd("K2555"), Some(2460800.5));
+        assert!(close(
+            orbit.perihelion_jd,
+            2460800.5 - 188.70269 / 0.21424651,
+            1e-9
+        ));
+
+        let unnamed = MinorBody::parse(&CERES[..160]).unwrap();
+        assert_eq!(unnamed.name, "00001");
+        assert!(MinorBody::parse("00001    not elements").is_err());
+    }
+
+    #[test]
+    fn solves_elliptic_orbits() {
+        // Meeus example 30.a: e = 0.1 and M = 5° give E = 5.554589°
+        let (e, ecc) = (0.1, 5.554589f64.to_radians());
+        let (nu, r) = at(5f64.to_radians() / GAUSS_K, 1.0 - e, e);
+        let expected = 2.0 * (((1.0 + e) / (1.0 - e)).sqrt() * (ecc / 2.0).tan()).atan();
+        assert!(close(nu, expected, 1e-7));
+        assert!(close(r, 1.0 - e * ecc.cos(), 1e-7));
+
+        // half a period on, a comet is at aphelion
+        let (q, e) = (0.330886f64, 0.85022);
+        let a = q / (1.0 - e);
+        let (nu, r) = at(std::f64::consts::PI * a.powf(1.5) / GAUSS_K, q, e);
+        assert!(close(nu.abs(), std::f64::consts::PI, 1e-6));
+        assert!(close(r, a * (1.0 + e), 1e-9));
+    }
+
+    #[test]
+    fn solves_parabolic_orbits() {
+        // Barker's equation: 90° from perihelion after √(2q³) × 4/3k days,
+        // at twice the perihelion distance
+        let q = 0.5f64;
+        let (nu, r) = at((2.0 * q * q * q).sqrt() * 4.0 / 3.0 / GAUSS_K, q, 1.0);
+        assert!(close(nu.to_degrees(), 90.0, 1e-6));
+        assert!(close(r, 2.0 * q, 1e-9));
+
+        let (nu, _) = at(-(2.0 * q * q * q).sqrt() * 4.0 / 3.0 / GAUSS_K, q, 1.0);
+        assert!(close(nu.to_degrees(), -90.0, 1e-6));
+    }
+
+    #[test]
+    fn solves_hyperbolic_orbits() {
+        // H = 1 gives M = e sinh H - H
+        let (q, e, hyp) = (1.2, 1.5, 1.0f64);
+        let a = q / (e - 1.0);
+        let mean_anomaly = e * hyp.sinh() - hyp;
+        let (nu, r) = at(mean_anomaly * a.powf(1.5) / GAUSS_K, q, e);
+        assert!(close(r, a * (e * hyp.cosh() - 1.0), 1e-9));
+        // and the distance follows from the anomaly as for any conic
+        assert!(close(r, q * (1.0 + e) / (1.0 + e * nu.cos()), 1e-9));
+    }
+
+    #[test]
+    fn encke_matches_the_reference_position() {
+        // Meeus example 33.a: 1990 October 6.0, α = 158.558965°,
+        // δ = +19.158496°, J2000
+        let encke = MinorBody::parse(ENCKE).unwrap();
+        let when = NaiveDate::from_ymd_opt(1990, 10, 6)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let ephemeris = encke.ephemeris(&when);
+        let (ra, dec) = precess(ephemeris.ra, ephemeris.dec, julian_date(&when), J2000);
+        let arcmin = 1.0 / 60.0;
+        assert!(
+            close(ra.to_degrees(), 158.558965, arcmin),
+            "{}",
+            ra.to_degrees()
+        );
+        assert!(
+            close(dec.to_degrees(), 19.158496, arcmin),
+            "{}",
+            dec.to_degrees()
+        );
+    }
+
+    #[test]
+    fn rise_and_set_are_left_out_when_they_dont_come() {
+        let encke = MinorBody::parse(ENCKE).unwrap();
+        let when = NaiveDate::from_ymd_opt(1990, 10, 6)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let position = |lat| Position {
+            lat,
+            lon: 0.0,
+            elevation: 0.0,
+        };
+        // at δ +19°, always up from near the north pole and never from
+        // near the south
+        let north = encke.target(&position(85.0), when);
+        assert_eq!((north.rising_time, north.setting_time), (None, None));
+        assert_eq!(north.missing_crossing(), "Always up");
+        let south = encke.target(&position(-85.0), when);
+        assert_eq!((south.rising_time, south.setting_time), (None, None));
+        assert_eq!(south.missing_crossing(), "Never rises");
+
+        let greenwich = encke.target(&position(51.5), when);
+        assert!(greenwich.rising_time.is_some() && greenwich.setting_time.is_some());
+        assert_eq!(greenwich.missing_crossing(), "—");
+    }
+}
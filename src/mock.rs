@@ -0,0 +1,155 @@
+//! Backend responses served from recorded fixtures, for working on the app
+//! with no network or backend. On with the `mock` feature, or with `?mock`
+//! in the page URL.
+
+use chrono::{Duration, NaiveDateTime};
+use web_sys::UrlSearchParams;
+
+use crate::{
+    almanac::crossing,
+    coords::{equatorial_to_horizontal, julian_date, local_sidereal_time, sun_equatorial},
+    errors::AppError,
+    models::{
+        AstronObject, AstronObjectQueryParams, AstronObjectResponse, SearchQueryParams,
+        SearchResponse,
+    },
+};
+
+const MOCK_URL_PARAM: &str = "mock";
+/// recorded with `scripts/record_fixtures.py`
+const ASTRON_OBJECTS_FIXTURE: &str = include_str!("../fixtures/astron_objects.json");
+const SEARCH_FIXTURE: &str = include_str!("../fixtures/search.json");
+/// how far ahead rising and setting are looked for, as for the server
+const RISE_SET_WINDOW_HOURS: i64 = 24;
+
+pub fn enabled() -> bool {
+    cfg!(feature = "mock")
+        || web_sys::window()
+            .and_then(|window| window.location().search().ok())
+            .and_then(|search| UrlSearchParams::new_with_str(&search).ok())
+            .is_some_and(|params| params.has(MOCK_URL_PARAM))
+}
+
+fn parse_fixture<T: serde::de::DeserializeOwned>(fixture: &str) -> Result<T, AppError> {
+    serde_json::from_str(fixture).map_err(|e| AppError::JsonError(e.to_string()))
+}
+
+/// Recorded places whose names contain the query, best match first.
+pub fn search(query: SearchQueryParams) -> Result<SearchResponse, AppError> {
+    let recorded: SearchResponse = parse_fixture(SEARCH_FIXTURE)?;
+    let q = query.q.trim().to_lowercase();
+    let mut items = recorded
+        .items
+        .into_iter()
+        .filter(|item| item.name.to_lowercase().contains(&q))
+        .collect::<Vec<_>>();
+    items.sort_by_key(|item| !item.name.to_lowercase().starts_with(&q));
+    items.truncate(query.max_results.max(0) as usize);
+    Ok(SearchResponse { items })
+}
+
+/// The recorded object moved to the query's position and time. Objects keep
+/// their recorded right ascension and declination, except the Sun, so the
+/// same query always gets the same answer and day follows night.
+pub fn get_astron_object_data(query: AstronObjectQueryParams) -> Result<AstronObjectResponse, AppError> {
+    let recorded: Vec<AstronObjectResponse> = parse_fixture(ASTRON_OBJECTS_FIXTURE)?;
+    let recorded = recorded
+        .into_iter()
+        .find(|obj| obj.name == query.name)
+        .ok_or_else(|| AppError::FetchError(format!("No fixture for {}", query.name)))?;
+
+    let equatorial = |t: &NaiveDateTime| match query.name {
+        AstronObject::Sun => sun_equatorial(julian_date(t)),
+        _ => (recorded.ra, recorded.dec),
+    };
+    let horizontal = |t: &NaiveDateTime| {
+        let (ra, dec) = equatorial(t);
+        let lst = local_sidereal_time(julian_date(t), query.lon.to_radians());
+        equatorial_to_horizontal(ra, dec, query.lat.to_radians(), lst)
+    };
+    let altitude = |t: &NaiveDateTime| horizontal(t).1.to_degrees();
+
+    let when = query.when;
+    let end = when + Duration::hours(RISE_SET_WINDOW_HOURS);
+    let (ra, dec) = equatorial(&when);
+    let (az, el) = horizontal(&when);
+    Ok(AstronObjectResponse {
+        az,
+        el,
+        ra,
+        dec,
+        setting_time: crossing(altitude, when, end, 0.0, true).unwrap_or(end),
+        rising_time: crossing(altitude, when, end, 0.0, false).unwrap_or(end),
+        when,
+        ..recorded
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use enum_iterator::all;
+
+    use super::*;
+
+    fn query(name: AstronObject, hour: u32) -> AstronObjectQueryParams {
+        AstronObjectQueryParams {
+            name,
+            lon: 151.2,
+            lat: -33.9,
+            elevation: 0.0,
+            when: NaiveDate::from_ymd_opt(2025, 6, 1)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn every_object_has_a_fixture() {
+        for name in all::<AstronObject>() {
+            let response = get_astron_object_data(query(name.clone(), 10)).unwrap();
+            assert_eq!(response.name, name);
+            assert_eq!(response.when, query(name, 10).when);
+            assert!(response.rising_time > response.when && response.setting_time > response.when);
+        }
+    }
+
+    #[test]
+    fn objects_are_deterministic_and_move_with_time() {
+        let first = get_astron_object_data(query(AstronObject::Jupiter, 10)).unwrap();
+        let again = get_astron_object_data(query(AstronObject::Jupiter, 10)).unwrap();
+        let later = get_astron_object_data(query(AstronObject::Jupiter, 12)).unwrap();
+        assert_eq!((first.az, first.el), (again.az, again.el));
+        assert_ne!((first.az, first.el), (later.az, later.el));
+        assert_eq!((first.ra, first.dec), (later.ra, later.dec));
+    }
+
+    #[test]
+    fn sun_is_up_by_day() {
+        // Sydney, 10:00 and 22:00 local time
+        assert!(get_astron_object_data(query(AstronObject::Sun, 0)).unwrap().el > 0.0);
+        assert!(get_astron_object_data(query(AstronObject::Sun, 12)).unwrap().el < 0.0);
+    }
+
+    #[test]
+    fn search_filters_recorded_places() {
+        let search = |q: &str, max_results| {
+            search(SearchQueryParams {
+                q: q.to_string(),
+                max_results,
+                fuzzy: 0.8,
+            })
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|item| item.name)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(search("syd", 5), ["Sydney"]);
+        assert_eq!(search("LONDON", 5), ["London"]);
+        // names starting with the query come first
+        assert_eq!(search("e", 2), ["Edinburgh", "Greenwich"]);
+        assert!(search("atlantis", 5).is_empty());
+    }
+}
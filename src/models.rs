@@ -6,7 +6,7 @@ use leptos::*;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
-use crate::errors::AppError;
+use crate::{errors::AppError, theme::ThemeKind};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchQueryParams {
@@ -15,7 +15,7 @@ pub struct SearchQueryParams {
     pub fuzzy: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SearchItem {
     pub country: String,
     pub sub_division: String,
@@ -59,8 +59,7 @@ extern "C" {
 
 fn geo_callback(position: JsValue) -> GeolocationCoordinates {
     let pos = JsCast::unchecked_into::<GeolocationPosition>(position);
-    let coords = pos.coords();
-    coords
+    pos.coords()
 }
 
 impl From<&GeolocationCoordinates> for Position {
@@ -89,7 +88,7 @@ impl Position {
                 set_coords.set(Some(Position::from(&geo_coords)));
             }) as Box<dyn Fn(JsValue)>);
             geolocation
-                .get_current_position(&geo_callback_function.as_ref().unchecked_ref())
+                .get_current_position(geo_callback_function.as_ref().unchecked_ref())
                 .expect("Unable to get position");
             // geolocation
             //     .watch_position(&geo_callback_function.as_ref().unchecked_ref())
@@ -114,6 +113,7 @@ pub struct AstronObjectQueryParams {
     pub when: NaiveDateTime,
 }
 
+/// The solar system objects the server has ephemerides for
 #[derive(Debug, Clone, Serialize, Deserialize, Sequence, PartialEq, Eq, Hash)]
 pub enum AstronObject {
     Sun,
@@ -129,12 +129,24 @@ pub enum AstronObject {
 
 impl AstronObject {
     /// get rgb color associated with this planet or planet-like object
-    pub fn get_color<'a>(&self) -> &'a str {
+    pub fn get_color<'a>(&self, theme: ThemeKind) -> &'a str {
+        if theme == ThemeKind::Print {
+            return "rgb(0,0,0)";
+        }
+        if theme == ThemeKind::Night {
+            // brighter reds for brighter objects, nothing outside the red
+            return match self {
+                Self::Sun => "rgb(239,68,68)",
+                Self::Moon | Self::Venus | Self::Jupiter => "rgb(220,38,38)",
+                _ => "rgb(185,28,28)",
+            };
+        }
         match self {
             Self::Sun => "rgb(255,204,0)",
             Self::Mercury => "rgb(215,179,119)",
             Self::Venus => "rgb(171,99,19)",
             Self::Mars => "rgb(114,47,18)",
+            Self::Moon if theme == ThemeKind::Dark => "rgba(200,200,200)",
             Self::Moon => "rgba(128,128,128)",
             Self::Jupiter => "rgb(150,81,46)",
             Self::Saturn => "rgb(215,179,119)",
@@ -174,10 +186,147 @@ pub struct AstronObjectResponse {
     pub when: NaiveDateTime,
 }
 
-#[derive(Debug, Clone)]
-pub struct SelectedAstronObjectResponse {
-    pub selected: bool,
-    pub obj: AstronObjectResponse,
+/// What sort of thing a sky target is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Sequence, PartialEq, Eq, Hash)]
+pub enum TargetKind {
+    Planet,
+    Moon,
+    Star,
+    DeepSky,
+    Satellite,
+    Comet,
+    Asteroid,
+    Custom,
+}
+
+impl TargetKind {
+    /// colour for targets of this kind without one of their own
+    pub fn get_color<'a>(&self, theme: ThemeKind) -> &'a str {
+        match theme {
+            ThemeKind::Print => "rgb(0,0,0)",
+            ThemeKind::Night => "rgb(185,28,28)",
+            _ => match self {
+                Self::Planet | Self::Moon => "rgb(128,128,128)",
+                Self::Star => "rgb(202,138,4)",
+                Self::DeepSky => "rgb(124,58,237)",
+                Self::Satellite => "rgb(100,116,139)",
+                Self::Comet => "rgb(94,176,140)",
+                Self::Asteroid => "rgb(120,113,108)",
+                Self::Custom => "rgb(219,39,119)",
+            },
+        }
+    }
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Planet => write!(f, "Planet"),
+            Self::Moon => write!(f, "Moon"),
+            Self::Star => write!(f, "Star"),
+            Self::DeepSky => write!(f, "Deep-sky object"),
+            Self::Satellite => write!(f, "Satellite"),
+            Self::Comet => write!(f, "Comet"),
+            Self::Asteroid => write!(f, "Asteroid"),
+            Self::Custom => write!(f, "Custom target"),
+        }
+    }
+}
+
+/// Identifies a sky target across refetches, eg `comet:1P/Halley`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct TargetId(pub String);
+
+impl TargetId {
+    pub fn new(kind: TargetKind, name: &str) -> Self {
+        Self(format!("{:?}:{}", kind, name).to_lowercase())
+    }
+
+    pub fn is_satellite(&self) -> bool {
+        self.0.starts_with("satellite:")
+    }
+}
+
+impl From<&AstronObject> for TargetId {
+    fn from(value: &AstronObject) -> Self {
+        Self(value.to_string().to_lowercase())
+    }
+}
+
+/// Anything that can be listed and plotted: a planet from the server, a
+/// comet, a star the user typed in. Angles are in radians and times are
+/// naive UTC, as in `AstronObjectResponse`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkyTarget {
+    pub id: TargetId,
+    pub kind: TargetKind,
+    pub name: String,
+    /// colour instead of the default for the body or kind
+    pub color: Option<String>,
+    /// the server's object, for the Sun, the Moon and the planets
+    pub body: Option<AstronObject>,
+    pub magnitude: f64,
+    pub size: f64,
+    pub az: f64,
+    pub el: f64,
+    pub ra: f64,
+    pub dec: f64,
+    /// next setting and rising in the day ahead, `None` if there isn't one
+    pub setting_time: Option<NaiveDateTime>,
+    pub rising_time: Option<NaiveDateTime>,
+    /// when it next crosses the meridian, where that is worked out here
+    pub transit_time: Option<NaiveDateTime>,
+    pub when: NaiveDateTime,
+}
+
+impl SkyTarget {
+    pub fn is(&self, body: &AstronObject) -> bool {
+        self.body.as_ref() == Some(body)
+    }
+
+    /// What to show in place of a rise or set time that doesn't come in the
+    /// day ahead
+    pub fn missing_crossing(&self) -> &'static str {
+        match (self.rising_time, self.setting_time) {
+            (None, None) if self.el > 0.0 => "Always up",
+            (None, None) => "Never rises",
+            _ => "—",
+        }
+    }
+
+    pub fn get_color(&self, theme: ThemeKind) -> String {
+        match (&self.color, &self.body) {
+            (Some(color), _) if !matches!(theme, ThemeKind::Night | ThemeKind::Print) => color.clone(),
+            (_, Some(body)) => body.get_color(theme).to_string(),
+            _ => self.kind.get_color(theme).to_string(),
+        }
+    }
+}
+
+impl From<AstronObjectResponse> for SkyTarget {
+    fn from(value: AstronObjectResponse) -> Self {
+        Self {
+            id: TargetId::from(&value.name),
+            kind: match value.name {
+                AstronObject::Sun => TargetKind::Star,
+                AstronObject::Moon => TargetKind::Moon,
+                _ => TargetKind::Planet,
+            },
+            name: value.name.to_string(),
+            color: None,
+            body: Some(value.name),
+            magnitude: value.magnitude,
+            size: value.size,
+            az: value.az,
+            el: value.el,
+            ra: value.ra,
+            dec: value.dec,
+            setting_time: Some(value.setting_time),
+            rising_time: Some(value.rising_time),
+            transit_time: None,
+            when: value.when,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -231,7 +380,150 @@ impl From<u8> for CardinalDirection {
             6 => Self::SouthWest,
             7 => Self::West,
             8 => Self::NorthWest,
-            _ => panic!("Can't match value greater than 7!"),
+            _ => panic!("Cardinal directions are numbered 1 to 8, not {}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    fn when() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 20)
+            .unwrap()
+            .and_hms_opt(21, 0, 0)
+            .unwrap()
+    }
+
+    fn response() -> AstronObjectResponse {
+        AstronObjectResponse {
+            name: AstronObject::Jupiter,
+            magnitude: -2.1,
+            size: 38.2,
+            az: 4.5,
+            el: 0.3,
+            ra: 0.8,
+            dec: 0.2,
+            setting_time: when() + chrono::Duration::hours(2),
+            rising_time: when() + chrono::Duration::hours(14),
+            when: when(),
         }
     }
+
+    #[test]
+    fn astron_objects_round_trip() {
+        for obj in enum_iterator::all::<AstronObject>() {
+            assert_eq!(round_trip(&obj), obj);
+        }
+        assert_eq!(serde_json::to_string(&AstronObject::Moon).unwrap(), "\"Moon\"");
+    }
+
+    #[test]
+    fn server_response_parses() {
+        let json = r#"{
+            "name": "Jupiter",
+            "magnitude": -2.1,
+            "size": 38.2,
+            "az": 4.5,
+            "el": 0.3,
+            "ra": 0.8,
+            "dec": 0.2,
+            "setting_time": "2024-03-20T23:00:00",
+            "rising_time": "2024-03-21T11:00:00",
+            "when": "2024-03-20T21:00:00"
+        }"#;
+        let parsed: AstronObjectResponse = serde_json::from_str(json).unwrap();
+        let expected = response();
+        assert_eq!(parsed.name, expected.name);
+        assert_eq!(parsed.setting_time, expected.setting_time);
+        assert_eq!(parsed.rising_time, expected.rising_time);
+        assert_eq!(parsed.when, expected.when);
+        assert_eq!(parsed.magnitude, expected.magnitude);
+
+        let again = round_trip(&parsed);
+        assert_eq!(again.name, parsed.name);
+        assert_eq!(again.az, parsed.az);
+        assert_eq!(again.when, parsed.when);
+    }
+
+    #[test]
+    fn query_params_serialize() {
+        let params = AstronObjectQueryParams {
+            name: AstronObject::Saturn,
+            lon: -0.1,
+            lat: 51.5,
+            elevation: 0.0,
+            when: when(),
+        };
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["name"], "Saturn");
+        assert_eq!(value["when"], "2024-03-20T21:00:00");
+        assert_eq!(round_trip(&params).lat, 51.5);
+    }
+
+    #[test]
+    fn search_response_round_trip() {
+        let response = SearchResponse {
+            items: vec![SearchItem {
+                country: "GB".to_string(),
+                sub_division: "England".to_string(),
+                name: "London".to_string(),
+                lat: 51.5,
+                lon: -0.1,
+            }],
+        };
+        assert_eq!(round_trip(&response).items, response.items);
+    }
+
+    #[test]
+    fn sky_target_round_trip() {
+        let target = SkyTarget::from(response());
+        assert_eq!(target.id, TargetId("jupiter".to_string()));
+        assert_eq!(target.kind, TargetKind::Planet);
+        assert_eq!(target.name, "Jupiter");
+        assert!(target.is(&AstronObject::Jupiter));
+        assert_eq!(round_trip(&target), target);
+
+        let custom = SkyTarget {
+            id: TargetId::new(TargetKind::Custom, "Betelgeuse"),
+            kind: TargetKind::Custom,
+            name: "Betelgeuse".to_string(),
+            color: Some("rgb(255,0,0)".to_string()),
+            body: None,
+            transit_time: Some(when()),
+            ..target
+        };
+        assert_eq!(custom.id.0, "custom:betelgeuse");
+        assert_eq!(round_trip(&custom), custom);
+    }
+
+    #[test]
+    fn sky_target_kinds() {
+        let kind = |name: AstronObject| {
+            SkyTarget::from(AstronObjectResponse { name, ..response() }).kind
+        };
+        assert_eq!(kind(AstronObject::Sun), TargetKind::Star);
+        assert_eq!(kind(AstronObject::Moon), TargetKind::Moon);
+        assert_eq!(kind(AstronObject::Mars), TargetKind::Planet);
+    }
+
+    #[test]
+    fn sky_target_colors() {
+        let mut target = SkyTarget::from(response());
+        assert_eq!(target.get_color(ThemeKind::Light), "rgb(150,81,46)");
+        target.color = Some("rgb(1,2,3)".to_string());
+        assert_eq!(target.get_color(ThemeKind::Light), "rgb(1,2,3)");
+        // night vision and print keep to their own palettes
+        assert_eq!(target.get_color(ThemeKind::Print), "rgb(0,0,0)");
+        target.body = None;
+        assert_eq!(target.get_color(ThemeKind::Night), "rgb(185,28,28)");
+    }
 }
@@ -0,0 +1,168 @@
+use std::f64::consts::TAU;
+
+use leptos::{ev::Custom, *};
+use leptos_use::{use_event_listener, use_window};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::DeviceOrientationEvent;
+
+use crate::errors::AppError;
+
+/// Where the back of the device is pointing, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceOrientation {
+    /// clockwise from north
+    pub heading: f64,
+    /// above the horizon
+    pub altitude: f64,
+}
+
+/// Compass heading of the back of the device from the Euler angles of a
+/// `deviceorientation` event, all in degrees. See the "worked example" in
+/// the W3C DeviceOrientation Event specification.
+pub fn compass_heading(alpha: f64, beta: f64, gamma: f64) -> f64 {
+    let (alpha, beta, gamma) = (alpha.to_radians(), beta.to_radians(), gamma.to_radians());
+    let r_a = -alpha.cos() * gamma.sin() - alpha.sin() * beta.sin() * gamma.cos();
+    let r_b = -alpha.sin() * gamma.sin() + alpha.cos() * beta.sin() * gamma.cos();
+    r_a.atan2(r_b).rem_euclid(TAU).to_degrees()
+}
+
+/// Altitude of the direction the back of the device points at: -90° lying
+/// flat on a table, 0° held upright, positive when tilted towards the sky.
+pub fn pointing_altitude(beta: f64, gamma: f64) -> f64 {
+    (-beta.to_radians().cos() * gamma.to_radians().cos())
+        .asin()
+        .to_degrees()
+}
+
+/// Shortest signed turn from `from` to `to`, in degrees in [-180, 180)
+pub fn relative_bearing(from: f64, to: f64) -> f64 {
+    (to - from + 540.0).rem_euclid(360.0) - 180.0
+}
+
+/// iOS only delivers orientation events after the user grants permission
+/// from a click handler. Other browsers don't have `requestPermission`.
+pub async fn request_orientation_permission() -> Result<(), AppError> {
+    let constructor = js_sys::Reflect::get(&window(), &JsValue::from_str("DeviceOrientationEvent"))
+        .map_err(|_| AppError::DomError("Device orientation is not supported".to_string()))?;
+    let request = js_sys::Reflect::get(&constructor, &JsValue::from_str("requestPermission"))
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+    let Some(request) = request else {
+        return Ok(());
+    };
+    let promise = request
+        .call0(&constructor)
+        .map_err(|_| AppError::DomError("Couldn't request orientation permission".to_string()))?;
+    let state = JsFuture::from(js_sys::Promise::from(promise))
+        .await
+        .map_err(|_| AppError::DomError("Orientation permission request failed".to_string()))?;
+    if state.as_string().as_deref() == Some("granted") {
+        Ok(())
+    } else {
+        Err(AppError::DomError("Orientation permission denied".to_string()))
+    }
+}
+
+/// Track the device orientation while `enabled` is true.
+///
+/// Prefers absolute readings (`deviceorientationabsolute` on Chrome, or
+/// `webkitCompassHeading` on Safari). Relative readings are only used while
+/// no absolute ones have arrived, which is what emulated sensors send.
+pub fn use_device_orientation(enabled: Signal<bool>) -> Signal<Option<DeviceOrientation>> {
+    let (orientation, set_orientation) = create_signal::<Option<DeviceOrientation>>(None);
+    let has_absolute = store_value(false);
+
+    let handle = move |ev: DeviceOrientationEvent, absolute: bool| {
+        if !enabled.get_untracked() {
+            return;
+        }
+        let (Some(alpha), Some(beta), Some(gamma)) = (ev.alpha(), ev.beta(), ev.gamma()) else {
+            return;
+        };
+        let webkit_heading = js_sys::Reflect::get(&ev, &JsValue::from_str("webkitCompassHeading"))
+            .ok()
+            .and_then(|h| h.as_f64());
+        let heading = match webkit_heading {
+            Some(heading) => {
+                has_absolute.set_value(true);
+                heading
+            }
+            None if absolute || ev.absolute() => {
+                has_absolute.set_value(true);
+                compass_heading(alpha, beta, gamma)
+            }
+            None if !has_absolute.get_value() => compass_heading(alpha, beta, gamma),
+            None => return,
+        };
+        set_orientation.set(Some(DeviceOrientation {
+            heading,
+            altitude: pointing_altitude(beta, gamma),
+        }));
+    };
+
+    let _ = use_event_listener(
+        use_window(),
+        Custom::<DeviceOrientationEvent>::new("deviceorientationabsolute"),
+        move |ev| handle(ev, true),
+    );
+    let _ = use_event_listener(
+        use_window(),
+        Custom::<DeviceOrientationEvent>::new("deviceorientation"),
+        move |ev| handle(ev, false),
+    );
+
+    create_effect(move |_| {
+        if !enabled.get() {
+            set_orientation.set(None);
+        }
+    });
+
+    orientation.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn flat_facing_north() {
+        assert_close(compass_heading(0.0, 0.0, 0.0), 0.0);
+        assert_close(pointing_altitude(0.0, 0.0), -90.0);
+    }
+
+    #[test]
+    fn upright_facing_east() {
+        // alpha turns counter-clockwise, so east is alpha = 270°
+        assert_close(compass_heading(270.0, 90.0, 0.0), 90.0);
+        assert_close(pointing_altitude(90.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn tilted_towards_the_sky() {
+        assert_close(pointing_altitude(120.0, 0.0), 30.0);
+    }
+
+    #[test]
+    fn heading_wraps_past_north() {
+        assert_close(compass_heading(10.0, 90.0, 0.0), 350.0);
+        assert_close(compass_heading(350.0, 90.0, 0.0), 10.0);
+    }
+
+    #[test]
+    fn bearing_takes_the_short_way_across_north() {
+        assert_close(relative_bearing(350.0, 10.0), 20.0);
+        assert_close(relative_bearing(10.0, 350.0), -20.0);
+        assert_close(relative_bearing(90.0, 270.0), -180.0);
+        assert_close(relative_bearing(45.0, 45.0), 0.0);
+    }
+}
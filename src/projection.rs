@@ -0,0 +1,205 @@
+use std::{
+    f64::consts::{FRAC_PI_2, FRAC_PI_4},
+    fmt,
+};
+
+use enum_iterator::Sequence;
+
+/// An azimuthal projection of the sky dome onto a disc.
+pub trait Projection {
+    /// Relative radius for a zenith distance `z` in radians, running from 0
+    /// at the zenith to 1 at the horizon.
+    fn radius(&self, z: f64) -> f64;
+}
+
+/// Conformal: shapes are preserved, the horizon is stretched.
+pub struct Stereographic;
+
+/// Distances from the zenith are preserved; elevation circles are evenly spaced.
+pub struct AzimuthalEquidistant;
+
+/// Lambert's projection: equal areas of sky get equal areas on screen.
+pub struct EqualArea;
+
+/// The dome as seen from far above; the zenith is enlarged, the horizon squashed.
+pub struct Orthographic;
+
+impl Projection for Stereographic {
+    fn radius(&self, z: f64) -> f64 {
+        (z / 2.0).tan() / FRAC_PI_4.tan()
+    }
+}
+
+impl Projection for AzimuthalEquidistant {
+    fn radius(&self, z: f64) -> f64 {
+        z / FRAC_PI_2
+    }
+}
+
+impl Projection for EqualArea {
+    fn radius(&self, z: f64) -> f64 {
+        (z / 2.0).sin() / FRAC_PI_4.sin()
+    }
+}
+
+impl Projection for Orthographic {
+    fn radius(&self, z: f64) -> f64 {
+        z.sin()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Sequence)]
+pub enum ProjectionKind {
+    Stereographic,
+    #[default]
+    AzimuthalEquidistant,
+    EqualArea,
+    Orthographic,
+}
+
+impl ProjectionKind {
+    pub fn projection(&self) -> &'static dyn Projection {
+        match self {
+            Self::Stereographic => &Stereographic,
+            Self::AzimuthalEquidistant => &AzimuthalEquidistant,
+            Self::EqualArea => &EqualArea,
+            Self::Orthographic => &Orthographic,
+        }
+    }
+}
+
+impl fmt::Display for ProjectionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stereographic => write!(f, "Stereographic"),
+            Self::AzimuthalEquidistant => write!(f, "Azimuthal equidistant"),
+            Self::EqualArea => write!(f, "Equal area"),
+            Self::Orthographic => write!(f, "Orthographic"),
+        }
+    }
+}
+
+/// Fraction of the plot radius taken up by the sky above the horizon when
+/// objects below the horizon are shown in an outer ring.
+pub const HORIZON_FRACTION: f64 = 0.8;
+
+/// Maps azimuth and elevation onto screen coordinates for a polar plot
+/// with the given screen `radius` and center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyTransform {
+    pub projection: ProjectionKind,
+    pub radius: f64,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub below_horizon: bool,
+}
+
+impl SkyTransform {
+    /// screen radius of the horizon circle
+    pub fn horizon_radius(&self) -> f64 {
+        if self.below_horizon {
+            self.radius * HORIZON_FRACTION
+        } else {
+            self.radius
+        }
+    }
+
+    /// Screen radius for an elevation in radians. Objects below the horizon
+    /// either go into the outer ring, spread linearly down to the nadir, or
+    /// sit on the horizon when the ring isn't shown.
+    pub fn el_radius(&self, el: f64) -> f64 {
+        let horizon = self.horizon_radius();
+        if el >= 0.0 {
+            horizon * self.projection.projection().radius(FRAC_PI_2 - el.min(FRAC_PI_2))
+        } else if self.below_horizon {
+            horizon + (self.radius - horizon) * (-el / FRAC_PI_2).min(1.0)
+        } else {
+            horizon
+        }
+    }
+
+    pub fn az_el(&self, az: f64, el: f64) -> (f64, f64) {
+        self.az_r(az, self.el_radius(el))
+    }
+
+    /// screen position for an azimuth and a radius relative to the plot radius
+    pub fn az_r_rel(&self, az: f64, r_rel: f64) -> (f64, f64) {
+        self.az_r(az, self.radius * r_rel)
+    }
+
+    fn az_r(&self, az: f64, r: f64) -> (f64, f64) {
+        let cx = r * (az - FRAC_PI_2).cos();
+        let cy = r * (az - FRAC_PI_2).sin();
+        (cx + self.center_x, cy + self.center_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use enum_iterator::all;
+
+    use super::*;
+
+    fn sky(projection: ProjectionKind, below_horizon: bool) -> SkyTransform {
+        SkyTransform {
+            projection,
+            radius: 100.0,
+            center_x: 150.0,
+            center_y: 120.0,
+            below_horizon,
+        }
+    }
+
+    fn assert_point(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn projections_run_from_zenith_to_horizon() {
+        for kind in all::<ProjectionKind>() {
+            let projection = kind.projection();
+            assert!(projection.radius(0.0).abs() < 1e-12, "{}", kind);
+            assert!((projection.radius(FRAC_PI_2) - 1.0).abs() < 1e-12, "{}", kind);
+            assert!(projection.radius(0.3) < projection.radius(0.6), "{}", kind);
+        }
+    }
+
+    #[test]
+    fn az_el_puts_north_up_and_east_right() {
+        for kind in all::<ProjectionKind>() {
+            let sky = sky(kind, false);
+            assert_point(sky.az_el(1.0, FRAC_PI_2), (150.0, 120.0));
+            assert_point(sky.az_el(0.0, 0.0), (150.0, 20.0));
+            assert_point(sky.az_el(FRAC_PI_2, 0.0), (250.0, 120.0));
+            assert_point(sky.az_el(PI, 0.0), (150.0, 220.0));
+            assert_point(sky.az_el(3.0 * FRAC_PI_2, 0.0), (50.0, 120.0));
+        }
+    }
+
+    #[test]
+    fn az_el_equidistant_elevations() {
+        let sky = sky(ProjectionKind::AzimuthalEquidistant, false);
+        assert_point(sky.az_el(FRAC_PI_2, PI / 4.0), (200.0, 120.0));
+        assert_point(sky.az_el(PI, PI / 6.0), (150.0, 120.0 + 200.0 / 3.0));
+    }
+
+    #[test]
+    fn az_el_below_horizon() {
+        // without the ring, everything below the horizon sits on it
+        let sky_without = sky(ProjectionKind::Stereographic, false);
+        assert_point(sky_without.az_el(0.0, -0.5), sky_without.az_el(0.0, 0.0));
+
+        let sky_with = sky(ProjectionKind::Stereographic, true);
+        assert_eq!(sky_with.horizon_radius(), 100.0 * HORIZON_FRACTION);
+        assert_point(sky_with.az_el(0.0, 0.0), (150.0, 120.0 - 100.0 * HORIZON_FRACTION));
+        assert_point(sky_with.az_el(0.0, -FRAC_PI_2), (150.0, 20.0));
+        assert_point(sky_with.az_el(0.0, -PI), (150.0, 20.0));
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    almanac::{sun_altitude, MoonPhase, Twilight, CIVIL_TWILIGHT},
+    coords::{angular_separation, equatorial_to_horizontal, julian_date, local_sidereal_time},
+    models::{AstronObject, Position, SkyTarget, TargetId},
+    utils::{deg2cardinal, naive_utc_to_local},
+};
+
+/// objects lower than this, in degrees, are lost in haze and buildings
+const MIN_USEFUL_ELEVATION: f64 = 10.0;
+const SAMPLE_MINUTES: i64 = 10;
+
+/// An object worth looking at tonight, with what makes it so.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    pub id: TargetId,
+    pub name: String,
+    /// 0 to 100, higher is better
+    pub score: f64,
+    /// plain-language reasons, most important first
+    pub reasons: Vec<String>,
+}
+
+impl Recommendation {
+    /// eg "Jupiter: high in the southeast after 21:30, very bright"
+    pub fn summary(&self) -> String {
+        format!("{}: {}", self.name, self.reasons.join(", "))
+    }
+}
+
+/// The dark part of the night around `when`, from the end of civil
+/// twilight to the start of the next one.
+pub fn darkness(position: &Position, when: NaiveDateTime) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let twilight = Twilight::for_night(position, when);
+    match (twilight.civil_dusk, twilight.civil_dawn) {
+        (Some(start), Some(end)) => Some((start.max(when), end)),
+        // polar night: dark all the time
+        _ if sun_altitude(&when, position) < CIVIL_TWILIGHT => Some((when, when + Duration::hours(12))),
+        _ => None,
+    }
+}
+
+/// (time, azimuth, elevation) in degrees every few minutes from `start` to
+/// `end`, holding RA and Dec fixed
+fn track(
+    obj: &SkyTarget,
+    position: &Position,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Vec<(NaiveDateTime, f64, f64)> {
+    let (lat, lon) = (position.lat.to_radians(), position.lon.to_radians());
+    let mut samples = Vec::new();
+    let mut t = start;
+    while t <= end {
+        let lst = local_sidereal_time(julian_date(&t), lon);
+        let (az, el) = equatorial_to_horizontal(obj.ra, obj.dec, lat, lst);
+        samples.push((t, az.to_degrees(), el.to_degrees()));
+        t += Duration::minutes(SAMPLE_MINUTES);
+    }
+    samples
+}
+
+fn brightness(magnitude: f64) -> &'static str {
+    match magnitude {
+        m if m < -2.0 => "very bright",
+        m if m < 0.5 => "bright",
+        m if m < 2.0 => "easy to see",
+        _ => "faint",
+    }
+}
+
+fn height(elevation: f64) -> &'static str {
+    match elevation {
+        el if el > 50.0 => "high",
+        el if el > 25.0 => "well up",
+        _ => "low",
+    }
+}
+
+/// Score `obj` for the dark hours from `start` to `end`. `None` if it never
+/// gets high enough to be worth it.
+fn recommend(
+    obj: &SkyTarget,
+    sun: &SkyTarget,
+    moon: Option<&SkyTarget>,
+    position: &Position,
+    (start, end): (NaiveDateTime, NaiveDateTime),
+) -> Option<Recommendation> {
+    let samples = track(obj, position, start, end);
+    let up: Vec<_> = samples
+        .iter()
+        .filter(|(_, _, el)| *el > MIN_USEFUL_ELEVATION)
+        .collect();
+    let (first, last) = (up.first()?, up.last()?);
+    let &&(_, peak_az, peak_el) = up.iter().max_by(|a, b| a.2.total_cmp(&b.2))?;
+
+    let altitude_score = ((peak_el - MIN_USEFUL_ELEVATION) / 50.0).clamp(0.0, 1.0);
+    let brightness_score = ((3.0 - obj.magnitude) / 7.0).clamp(0.0, 1.0);
+    let duration_score = up.len() as f64 / samples.len() as f64;
+
+    // a bright Moon nearby washes fainter objects out
+    let moon_glare = match moon {
+        Some(moon) if !obj.is(&AstronObject::Moon) => {
+            let separation = angular_separation(obj.ra, obj.dec, moon.ra, moon.dec).to_degrees();
+            MoonPhase::new(sun, moon).illuminated * (1.0 - separation / 30.0).clamp(0.0, 1.0)
+        }
+        _ => 0.0,
+    };
+    // close to the Sun means only ever seen low in bright twilight
+    let elongation = angular_separation(obj.ra, obj.dec, sun.ra, sun.dec).to_degrees();
+    let sun_factor = ((elongation - 10.0) / 20.0).clamp(0.0, 1.0);
+
+    let score = 100.0
+        * sun_factor
+        * (1.0 - 0.5 * moon_glare)
+        * (0.45 * altitude_score + 0.35 * brightness_score + 0.2 * duration_score);
+    if score <= 0.0 {
+        return None;
+    }
+
+    let format_time = |t: &NaiveDateTime| naive_utc_to_local(t).format("%H:%M").to_string();
+    let when = match (first.0 == samples[0].0, last.0 == samples[samples.len() - 1].0) {
+        (true, true) => "all night".to_string(),
+        (true, false) => format!("until {}", format_time(&last.0)),
+        (false, true) => format!("after {}", format_time(&first.0)),
+        (false, false) => format!("from {} to {}", format_time(&first.0), format_time(&last.0)),
+    };
+    let mut reasons = vec![
+        format!(
+            "{} in the {} {}",
+            height(peak_el),
+            deg2cardinal(peak_az).to_string().to_lowercase(),
+            when
+        ),
+        brightness(obj.magnitude).to_string(),
+    ];
+    if obj.is(&AstronObject::Moon) {
+        if let Some(moon) = moon {
+            reasons.push(MoonPhase::new(sun, moon).name().to_lowercase());
+        }
+    }
+    if moon_glare > 0.3 {
+        reasons.push("close to a bright Moon".to_string());
+    }
+    if elongation < 25.0 {
+        reasons.push("close to the Sun, look in twilight".to_string());
+    }
+
+    Some(Recommendation {
+        id: obj.id.clone(),
+        name: obj.name.clone(),
+        score,
+        reasons,
+    })
+}
+
+/// Objects worth looking at tonight from `position`, best first.
+pub fn best_tonight(objs: &[SkyTarget], position: &Position) -> Vec<Recommendation> {
+    let find = |body: AstronObject| objs.iter().find(|obj| obj.is(&body));
+    let Some(sun) = find(AstronObject::Sun) else {
+        return Vec::new();
+    };
+    let Some(window) = darkness(position, sun.when) else {
+        return Vec::new();
+    };
+    let moon = find(AstronObject::Moon);
+
+    let mut recommendations: Vec<_> = objs
+        .iter()
+        .filter(|obj| !obj.is(&AstronObject::Sun))
+        .filter_map(|obj| recommend(obj, sun, moon, position, window))
+        .collect();
+    recommendations.sort_by(|a, b| b.score.total_cmp(&a.score));
+    recommendations
+}
@@ -0,0 +1,660 @@
+use std::{
+    f64::consts::{PI, TAU},
+    fmt,
+    rc::Rc,
+};
+
+use chrono::{Duration, DurationRound, NaiveDateTime};
+use leptos::*;
+use leptos_use::storage::{use_local_storage, StringCodec};
+
+use crate::{
+    almanac::{sun_altitude, CIVIL_TWILIGHT},
+    coords::{gmst, julian_date, sun_equatorial},
+    errors::AppError,
+    models::Position,
+    state::use_app_state,
+};
+
+const BUNDLED_TLE_URL: &str = "/satellites.tle";
+/// Celestrak's list of the brightest satellites
+pub const DEFAULT_TLE_URL: &str =
+    "https://celestrak.org/NORAD/elements/gp.php?GROUP=visual&FORMAT=tle";
+const TLE_URL_STORAGE_KEY: &str = "planet-tracker-tle-url";
+
+/// WGS 84
+const EARTH_RADIUS_KM: f64 = 6378.137;
+const EARTH_FLATTENING: f64 = 1.0 / 298.257223563;
+
+/// how far ahead passes are shown
+const PASS_WINDOW_HOURS: i64 = 24;
+/// passes are predicted for windows starting on the hour, so that moving
+/// the time within it doesn't predict them all again
+const PASS_WINDOW_ALIGN_HOURS: i64 = 1;
+const PASS_STEP_SECONDS: i64 = 60;
+/// passes lower than this are lost in the murk near the horizon, in degrees
+const MIN_PASS_ELEVATION: f64 = 10.0;
+/// SGP4 errors grow by a few km a day; past this the predicted times may
+/// be minutes out
+pub const MAX_ELEMENT_AGE_DAYS: f64 = 30.0;
+/// past this the satellite may be tens of minutes from where SGP4 puts it,
+/// if it's still up at all, so it gets no passes
+pub const MAX_PREDICTION_AGE_DAYS: f64 = 90.0;
+
+/// Standard magnitude, at 1000 km and half lit, of satellites with a known
+/// one, by NORAD catalogue number
+const STANDARD_MAGNITUDES: [(u64, f64); 1] = [(25544, -1.8)];
+/// about that of the brighter satellites in Celestrak's visual list
+const DEFAULT_STANDARD_MAGNITUDE: f64 = 4.0;
+
+/// One satellite's orbital elements, ready to propagate with SGP4.
+#[derive(Clone)]
+pub struct Satellite {
+    pub name: String,
+    pub norad_id: u64,
+    /// epoch of the elements, UTC
+    pub epoch: NaiveDateTime,
+    standard_magnitude: f64,
+    constants: Rc<sgp4::Constants>,
+}
+
+impl fmt::Debug for Satellite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Satellite")
+            .field("name", &self.name)
+            .field("norad_id", &self.norad_id)
+            .field("epoch", &self.epoch)
+            .finish()
+    }
+}
+
+/// Where a satellite is seen from the observer at `when` (UTC). `az` and
+/// `el` are in radians, like the server's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PassPoint {
+    pub when: NaiveDateTime,
+    pub az: f64,
+    pub el: f64,
+    /// in sunlight rather than the Earth's shadow
+    pub sunlit: bool,
+    /// estimated, if sunlit
+    pub magnitude: Option<f64>,
+}
+
+/// One pass over the observer's sky, from rising above the horizon to
+/// setting below it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SatellitePass {
+    pub satellite: String,
+    pub start: PassPoint,
+    pub max: PassPoint,
+    pub end: PassPoint,
+    /// points every `PASS_STEP_SECONDS`, from `start` to `end`
+    pub track: Vec<PassPoint>,
+    /// sunlit while the observer's sky is dark, at some point
+    pub visible: bool,
+    /// brightest while visible
+    pub magnitude: Option<f64>,
+}
+
+impl SatellitePass {
+    pub fn id(&self) -> String {
+        format!("{}-{}", self.satellite, self.start.when.and_utc().timestamp())
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Observer position in km in the TEME frame SGP4 works in, where
+/// `sidereal` is the local sidereal time in radians
+fn observer_teme(position: &Position, sidereal: f64) -> [f64; 3] {
+    let lat = position.lat.to_radians();
+    let e2 = EARTH_FLATTENING * (2.0 - EARTH_FLATTENING);
+    let n = EARTH_RADIUS_KM / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+    let h = position.elevation / 1000.0;
+    [
+        (n + h) * lat.cos() * sidereal.cos(),
+        (n + h) * lat.cos() * sidereal.sin(),
+        (n * (1.0 - e2) + h) * lat.sin(),
+    ]
+}
+
+/// Unit vector towards the Sun
+fn sun_direction(jd: f64) -> [f64; 3] {
+    let (ra, dec) = sun_equatorial(jd);
+    [dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin()]
+}
+
+/// Outside the Earth's shadow, taken as a cylinder
+fn in_sunlight(satellite: [f64; 3], sun: [f64; 3]) -> bool {
+    let along = dot(satellite, sun);
+    along > 0.0 || norm(sub(satellite, sun.map(|c| c * along))) > EARTH_RADIUS_KM
+}
+
+impl Satellite {
+    fn from_tle(name: Option<String>, line1: &str, line2: &str) -> Result<Self, AppError> {
+        let invalid = |e: &dyn fmt::Debug| AppError::ParseError(format!("Invalid TLE: {:?}", e));
+        let elements = sgp4::Elements::from_tle(name, line1.as_bytes(), line2.as_bytes())
+            .map_err(|e| invalid(&e))?;
+        let constants = sgp4::Constants::from_elements(&elements).map_err(|e| invalid(&e))?;
+        let standard_magnitude = STANDARD_MAGNITUDES
+            .iter()
+            .find(|(id, _)| *id == elements.norad_id)
+            .map_or(DEFAULT_STANDARD_MAGNITUDE, |(_, mag)| *mag);
+        Ok(Self {
+            name: elements
+                .object_name
+                .clone()
+                .unwrap_or_else(|| format!("NORAD {}", elements.norad_id)),
+            norad_id: elements.norad_id,
+            epoch: elements.datetime,
+            standard_magnitude,
+            constants: Rc::new(constants),
+        })
+    }
+
+    /// days between the epoch of the elements and `when`
+    pub fn age_days(&self, when: &NaiveDateTime) -> f64 {
+        (*when - self.epoch).num_seconds() as f64 / 86400.0
+    }
+
+    /// whether the elements are recent enough at `when` to predict passes
+    pub fn predictable(&self, when: &NaiveDateTime) -> bool {
+        self.age_days(when).abs() <= MAX_PREDICTION_AGE_DAYS
+    }
+
+    /// Where the satellite appears from `position` at `when`, or `None` if
+    /// SGP4 fails, as it does once the orbit has decayed.
+    pub fn look(&self, position: &Position, when: NaiveDateTime) -> Option<PassPoint> {
+        let minutes = (when - self.epoch).num_milliseconds() as f64 / 60_000.0;
+        let satellite = self
+            .constants
+            .propagate(sgp4::MinutesSinceEpoch(minutes))
+            .ok()?
+            .position;
+        let jd = julian_date(&when);
+        let lat = position.lat.to_radians();
+        let sidereal = (gmst(jd) + position.lon.to_radians()).rem_euclid(TAU);
+        let range = sub(satellite, observer_teme(position, sidereal));
+        let distance = norm(range);
+
+        // topocentric south, east and up
+        let (sin_lat, cos_lat) = lat.sin_cos();
+        let (sin_st, cos_st) = sidereal.sin_cos();
+        let south = sin_lat * cos_st * range[0] + sin_lat * sin_st * range[1] - cos_lat * range[2];
+        let east = -sin_st * range[0] + cos_st * range[1];
+        let up = cos_lat * cos_st * range[0] + cos_lat * sin_st * range[1] + sin_lat * range[2];
+
+        let sun = sun_direction(jd);
+        let sunlit = in_sunlight(satellite, sun);
+        // phase angle at the satellite between the Sun and the observer
+        let phase = (-dot(sun, range) / distance).clamp(-1.0, 1.0).acos();
+        let magnitude = sunlit.then(|| {
+            let lit = phase.sin() + (PI - phase) * phase.cos();
+            self.standard_magnitude - 15.0 + 5.0 * distance.log10() - 2.5 * lit.max(1e-3).log10()
+        });
+
+        Some(PassPoint {
+            when,
+            az: east.atan2(-south).rem_euclid(TAU),
+            el: (up / distance).asin(),
+            sunlit,
+            magnitude,
+        })
+    }
+
+    /// The time in [`lo`, `hi`] that the satellite crosses the horizon, to
+    /// within a second
+    fn horizon_crossing(
+        &self,
+        position: &Position,
+        lo: NaiveDateTime,
+        hi: NaiveDateTime,
+    ) -> Option<PassPoint> {
+        let above = |t: NaiveDateTime| self.look(position, t).is_some_and(|p| p.el > 0.0);
+        let rising = !above(lo);
+        let (mut lo, mut hi) = (lo, hi);
+        while hi - lo > Duration::seconds(1) {
+            let mid = lo + (hi - lo) / 2;
+            if above(mid) == rising {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        self.look(position, hi)
+    }
+
+    fn pass(
+        &self,
+        position: &Position,
+        track: Vec<PassPoint>,
+        before: Option<NaiveDateTime>,
+        after: Option<NaiveDateTime>,
+    ) -> Option<SatellitePass> {
+        let first = *track.first()?;
+        let last = *track.last()?;
+        let max = *track.iter().max_by(|a, b| a.el.total_cmp(&b.el))?;
+        if max.el.to_degrees() < MIN_PASS_ELEVATION {
+            return None;
+        }
+        let start = before
+            .and_then(|before| self.horizon_crossing(position, before, first.when))
+            .unwrap_or(first);
+        let end = after
+            .and_then(|after| self.horizon_crossing(position, last.when, after))
+            .unwrap_or(last);
+        let dark = |point: &&PassPoint| {
+            point.sunlit && sun_altitude(&point.when, position) < CIVIL_TWILIGHT
+        };
+        let magnitude = track
+            .iter()
+            .filter(dark)
+            .filter_map(|point| point.magnitude)
+            .min_by(|a, b| a.total_cmp(b));
+        Some(SatellitePass {
+            satellite: self.name.clone(),
+            start,
+            max,
+            end,
+            visible: track.iter().any(|point| dark(&point)),
+            magnitude,
+            track,
+        })
+    }
+
+    /// Passes over `position` between `start` and `end` (UTC) that reach
+    /// `MIN_PASS_ELEVATION`
+    pub fn passes(
+        &self,
+        position: &Position,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<SatellitePass> {
+        let step = Duration::seconds(PASS_STEP_SECONDS);
+        let mut passes = Vec::new();
+        let mut track = Vec::new();
+        let mut before = None;
+        let mut t = start;
+        while t <= end {
+            let Some(point) = self.look(position, t) else {
+                break;
+            };
+            if point.el > 0.0 {
+                track.push(point);
+            } else {
+                if !track.is_empty() {
+                    passes.extend(self.pass(position, std::mem::take(&mut track), before, Some(t)));
+                }
+                before = Some(t);
+            }
+            t += step;
+        }
+        // still up at the end of the window
+        passes.extend(self.pass(position, track, before, None));
+        passes
+    }
+}
+
+/// Read satellites from Two-Line Element sets, with or without a name line
+/// before each. Sets that don't parse are skipped.
+pub fn parse_tle(text: &str) -> Result<Vec<Satellite>, AppError> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let mut satellites = Vec::new();
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        if lines[i].starts_with("1 ") && lines[i + 1].starts_with("2 ") {
+            let name = (i > 0 && !lines[i - 1].starts_with("2 "))
+                .then(|| lines[i - 1].trim().trim_start_matches("0 ").to_string());
+            match Satellite::from_tle(name, lines[i], lines[i + 1]) {
+                Ok(satellite) => satellites.push(satellite),
+                Err(e) => logging::warn!("skipping satellite: {}", e),
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    if satellites.is_empty() {
+        return Err(AppError::ParseError(
+            "No Two-Line Element sets found".to_string(),
+        ));
+    }
+    Ok(satellites)
+}
+
+/// The start of the prediction window holding `when`
+fn pass_window_start(when: NaiveDateTime) -> NaiveDateTime {
+    when.duration_trunc(Duration::hours(PASS_WINDOW_ALIGN_HOURS))
+        .unwrap_or(when)
+}
+
+/// Passes of the `satellites` with recent enough elements over `position`
+/// in the window from `start`, soonest first. It runs long enough to cover `PASS_WINDOW_HOURS` from any
+/// time before the next window starts.
+fn window_passes(
+    satellites: &[Satellite],
+    position: &Position,
+    start: NaiveDateTime,
+) -> Vec<SatellitePass> {
+    let end = start + Duration::hours(PASS_WINDOW_HOURS + PASS_WINDOW_ALIGN_HOURS);
+    let mut passes: Vec<SatellitePass> = satellites
+        .iter()
+        .filter(|satellite| satellite.predictable(&start))
+        .flat_map(|satellite| satellite.passes(position, start, end))
+        .collect();
+    passes.sort_by_key(|pass| pass.start.when);
+    passes
+}
+
+/// Those of `passes` still to end in the day from `when`
+pub fn upcoming_passes(passes: &[SatellitePass], when: NaiveDateTime) -> Vec<SatellitePass> {
+    let end = when + Duration::hours(PASS_WINDOW_HOURS);
+    passes
+        .iter()
+        .filter(|pass| pass.end.when >= when && pass.start.when <= end)
+        .cloned()
+        .collect()
+}
+
+async fn fetch_tle(url: &str) -> Result<String, AppError> {
+    reqwest::get(url)
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| AppError::FetchError(e.to_string()))?
+        .text()
+        .await
+        .map_err(|e| AppError::FetchError(e.to_string()))
+}
+
+/// Satellite elements and the passes they give for the current position
+/// and time.
+#[derive(Debug, Clone, Copy)]
+pub struct Satellites {
+    pub satellites: RwSignal<Rc<Vec<Satellite>>>,
+    /// where the elements came from, for display
+    pub source: RwSignal<String>,
+    pub error: RwSignal<Option<AppError>>,
+    /// endpoint to fetch elements from; empty for `DEFAULT_TLE_URL`
+    pub url: Signal<String>,
+    pub set_url: WriteSignal<String>,
+    pub passes: Memo<Vec<SatellitePass>>,
+    pub selected: RwSignal<Option<String>>,
+}
+
+impl Satellites {
+    /// no elements yet, with passes for the position and time in context
+    /// once there are some
+    fn new() -> Self {
+        let position_time = use_app_state().position_time;
+        let (url, set_url, _) = use_local_storage::<String, StringCodec>(TLE_URL_STORAGE_KEY);
+        let satellites = create_rw_signal(Rc::new(Vec::<Satellite>::new()));
+
+        // SGP4 only runs again when the position or the window changes
+        let window = create_memo(move |_| {
+            let (position, when) = position_time.get();
+            (position, pass_window_start(when.naive_utc()))
+        });
+        let predicted = create_memo(move |_| {
+            let (position, start) = window.get();
+            satellites.with(|satellites| window_passes(satellites, &position, start))
+        });
+        let passes = create_memo(move |_| {
+            let when = position_time.get().1.naive_utc();
+            predicted.with(|predicted| upcoming_passes(predicted, when))
+        });
+
+        Self {
+            satellites,
+            source: create_rw_signal(String::new()),
+            error: create_rw_signal(None),
+            url,
+            set_url,
+            passes,
+            selected: create_rw_signal(None),
+        }
+    }
+
+    /// use elements from `text`, eg an uploaded file, if any parse
+    pub fn load_text(&self, text: &str, source: String) {
+        match parse_tle(text) {
+            Ok(satellites) => {
+                self.satellites.set(Rc::new(satellites));
+                self.source.set(source);
+                self.error.set(None);
+            }
+            Err(e) => self.error.set(Some(e)),
+        }
+    }
+
+    /// Fetch elements from the configured endpoint, falling back to the
+    /// bundled ones if that fails.
+    pub fn fetch(&self) {
+        let this = *self;
+        let url = match self.url.get_untracked() {
+            url if url.trim().is_empty() => DEFAULT_TLE_URL.to_string(),
+            url => url.trim().to_string(),
+        };
+        spawn_local(async move {
+            let fetched = fetch_tle(&url).await.and_then(|text| parse_tle(&text));
+            match fetched {
+                Ok(satellites) => {
+                    this.satellites.set(Rc::new(satellites));
+                    this.source.set(url);
+                    this.error.set(None);
+                }
+                Err(e) => {
+                    logging::warn!("couldn't fetch satellite elements from {}: {}", url, e);
+                    let bundled = format!(
+                        "{}{}",
+                        window().location().origin().unwrap_or_default(),
+                        BUNDLED_TLE_URL
+                    );
+                    match fetch_tle(&bundled).await {
+                        Ok(text) => this.load_text(&text, "bundled elements".to_string()),
+                        Err(_) => this.error.set(Some(e)),
+                    }
+                }
+            }
+        });
+    }
+}
+
+pub fn use_satellites() -> Satellites {
+    use_context::<Satellites>()
+        .expect("Satellites missing from context, provide_satellites() must run in an ancestor")
+}
+
+/// Fetch satellite elements and provide `Satellites`, with passes for the
+/// position and time in context.
+pub fn provide_satellites() {
+    let context = Satellites::new();
+    context.fetch();
+    provide_context(context);
+}
+
+/// Provide `Satellites` without fetching any elements, so that mounting
+/// components in tests doesn't reach out to Celestrak
+#[cfg(all(test, target_arch = "wasm32"))]
+pub fn provide_empty_satellites() {
+    provide_context(Satellites::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Timelike};
+
+    use super::*;
+
+    const ISS: &str = "ISS (ZARYA)
+1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992
+2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008
+";
+    const VANGUARD: &str = "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753
+2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667
+";
+
+    fn iss() -> Satellite {
+        parse_tle(ISS).unwrap().remove(0)
+    }
+
+    fn greenwich() -> Position {
+        Position {
+            lat: 51.4769,
+            lon: -0.0005,
+            elevation: 0.0,
+        }
+    }
+
+    fn utc(hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2020, 7, 13)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap()
+    }
+
+    /// the point on the ground below the satellite at `when`
+    fn below(satellite: &Satellite, when: NaiveDateTime) -> Position {
+        let minutes = (when - satellite.epoch).num_seconds() as f64 / 60.0;
+        let [x, y, z] = satellite
+            .constants
+            .propagate(sgp4::MinutesSinceEpoch(minutes))
+            .unwrap()
+            .position;
+        let e2 = EARTH_FLATTENING * (2.0 - EARTH_FLATTENING);
+        let lon = (y.atan2(x) - gmst(julian_date(&when))).to_degrees();
+        Position {
+            lat: (z / (x.hypot(y) * (1.0 - e2))).atan().to_degrees(),
+            lon: (lon + 180.0).rem_euclid(360.0) - 180.0,
+            elevation: 0.0,
+        }
+    }
+
+    #[test]
+    fn parse_tle_reads_named_and_bare_sets() {
+        let broken = "1 00000U broken\n2 00000 broken\n";
+        let text = format!("{}{}0 VANGUARD 1\n{}{}", broken, ISS, VANGUARD, VANGUARD);
+        let satellites = parse_tle(&text).unwrap();
+        let names: Vec<&str> = satellites.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["ISS (ZARYA)", "VANGUARD 1", "NORAD 5"]);
+        assert_eq!(satellites[0].norad_id, 25544);
+        assert_eq!(satellites[1].norad_id, 5);
+        assert_eq!(
+            satellites[0].epoch.with_nanosecond(0).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 7, 12)
+                .unwrap()
+                .and_hms_opt(21, 16, 1)
+                .unwrap()
+        );
+        assert!(parse_tle("ISS (ZARYA)\nno elements here").is_err());
+    }
+
+    #[test]
+    fn look_finds_the_satellite_overhead_and_to_the_north() {
+        let iss = iss();
+        let when = utc(0, 0, 0);
+        let below = below(&iss, when);
+        assert!(iss.look(&below, when).unwrap().el.to_degrees() > 88.0);
+
+        let south = Position {
+            lat: below.lat - 5.0,
+            ..below.clone()
+        };
+        let point = iss.look(&south, when).unwrap();
+        let az = point.az.to_degrees();
+        assert!(az.min(360.0 - az) < 2.0, "az {}", az);
+        assert!(
+            (20.0..60.0).contains(&point.el.to_degrees()),
+            "el {}",
+            point.el.to_degrees()
+        );
+
+        let antipode = Position {
+            lat: -below.lat,
+            lon: below.lon + 180.0,
+            ..below
+        };
+        assert!(iss.look(&antipode, when).unwrap().el < 0.0);
+    }
+
+    #[test]
+    fn passes_finds_the_pass_overhead() {
+        let iss = iss();
+        let when = utc(0, 0, 0);
+        let passes = iss.passes(
+            &below(&iss, when),
+            utc(23, 40, 0) - Duration::days(1),
+            utc(0, 20, 0),
+        );
+        assert_eq!(passes.len(), 1);
+        let pass = &passes[0];
+        assert_eq!(pass.satellite, "ISS (ZARYA)");
+        assert!(pass.max.el.to_degrees() > 85.0);
+        assert!((pass.max.when - when).num_seconds().abs() <= 60);
+        assert!(pass.start.when < when && when < pass.end.when);
+        let minutes = (pass.end.when - pass.start.when).num_minutes();
+        assert!((8..=12).contains(&minutes), "{} minutes", minutes);
+        // rise and set are found between the steps, right at the horizon
+        assert!(pass.start.el.to_degrees().abs() < 0.5);
+        assert!(pass.end.el.to_degrees().abs() < 0.5);
+    }
+
+    #[test]
+    fn passes_from_the_window_match_predicting_from_the_time() {
+        let satellites = vec![iss()];
+        let greenwich = greenwich();
+        let when = utc(0, 40, 0);
+        let start = pass_window_start(when);
+        assert_eq!(start, utc(0, 0, 0));
+
+        let windowed = upcoming_passes(&window_passes(&satellites, &greenwich, start), when);
+        let direct =
+            satellites[0].passes(&greenwich, when, when + Duration::hours(PASS_WINDOW_HOURS));
+        // the window also finds where a pass already up at `when` rose
+        let in_progress = &windowed[0];
+        assert!(in_progress.start.when < when && when < in_progress.end.when);
+        let settled = |passes: &[SatellitePass]| -> Vec<NaiveDateTime> {
+            passes
+                .iter()
+                .filter(|pass| pass.start.when > when)
+                .filter(|pass| pass.end.when < when + Duration::hours(PASS_WINDOW_HOURS - 1))
+                .map(|pass| pass.start.when)
+                .collect()
+        };
+        assert!(!direct.is_empty());
+        assert_eq!(settled(&windowed), settled(&direct));
+    }
+
+    #[test]
+    fn old_elements_still_give_passes() {
+        let iss = iss();
+        let when = iss.epoch + Duration::days(40);
+        assert!(iss.age_days(&when) > MAX_ELEMENT_AGE_DAYS);
+        assert!(!window_passes(&[iss], &greenwich(), pass_window_start(when)).is_empty());
+    }
+
+    #[test]
+    fn far_too_old_elements_give_no_passes() {
+        let iss = iss();
+        for days in [-100, 100, 2000] {
+            let when = iss.epoch + Duration::days(days);
+            assert!(!iss.predictable(&when));
+            let start = pass_window_start(when);
+            assert!(window_passes(std::slice::from_ref(&iss), &greenwich(), start).is_empty());
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use leptos::*;
+use leptos_use::{use_service_worker_with_options, UseServiceWorkerOptions};
+use web_sys::ServiceWorkerRegistration;
+
+const SERVICE_WORKER_URL: &str = "/service-worker.js";
+
+/// The app's service worker registration, once there is one.
+#[derive(Debug, Clone, Copy)]
+pub struct ServiceWorker {
+    pub registration: Signal<Option<ServiceWorkerRegistration>>,
+}
+
+pub fn use_service_worker() -> ServiceWorker {
+    use_context::<ServiceWorker>()
+        .expect("ServiceWorker missing from context, provide_service_worker() must run in an ancestor")
+}
+
+/// Register the service worker, which caches the app for offline use and
+/// shows notifications, and provide its `ServiceWorker` context.
+pub fn provide_service_worker() {
+    let service_worker = use_service_worker_with_options(
+        UseServiceWorkerOptions::default()
+            .script_url(SERVICE_WORKER_URL)
+            // pages are fetched network first, so a new worker needs no reload
+            .on_controller_change(|| ()),
+    );
+    let registration = service_worker.registration;
+
+    provide_context(ServiceWorker {
+        registration: Signal::derive(move || registration.get().ok()),
+    });
+}
@@ -0,0 +1,311 @@
+//! The position, time, selection and layers the whole app shows, changed
+//! only through `Action`s so every change can be undone.
+
+use chrono::{DateTime, Utc};
+use leptos::*;
+use leptos_use::storage::{use_local_storage, JsonCodec};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Position, TargetId};
+
+const LAYERS_STORAGE_KEY: &str = "planet-tracker-layers";
+/// oldest changes are forgotten beyond this many
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    BelowHorizon,
+    Meridian,
+}
+
+/// optional overlays on the sky plots
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Layers {
+    pub below_horizon: bool,
+    pub meridian: bool,
+}
+
+impl Layers {
+    pub fn shows(&self, layer: Layer) -> bool {
+        match layer {
+            Layer::BelowHorizon => self.below_horizon,
+            Layer::Meridian => self.meridian,
+        }
+    }
+
+    fn toggle(&mut self, layer: Layer) {
+        match layer {
+            Layer::BelowHorizon => self.below_horizon = !self.below_horizon,
+            Layer::Meridian => self.meridian = !self.meridian,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    SetPosition(Position),
+    SetTime(DateTime<Utc>),
+    Select(Option<TargetId>),
+    /// select the target, or clear the selection if it is already selected
+    ToggleSelected(TargetId),
+    ToggleLayer(Layer),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewState {
+    pub position: Position,
+    pub time: DateTime<Utc>,
+    pub selected: Option<TargetId>,
+    pub layers: Layers,
+}
+
+impl ViewState {
+    pub fn new(position: Position, time: DateTime<Utc>) -> Self {
+        Self {
+            position,
+            time,
+            selected: None,
+            layers: Layers::default(),
+        }
+    }
+
+    pub fn reduce(&self, action: Action) -> Self {
+        let mut next = self.clone();
+        match action {
+            Action::SetPosition(position) => next.position = position,
+            Action::SetTime(time) => next.time = time,
+            Action::Select(selected) => next.selected = selected,
+            Action::ToggleSelected(id) => {
+                next.selected = (self.selected.as_ref() != Some(&id)).then_some(id)
+            }
+            Action::ToggleLayer(layer) => next.layers.toggle(layer),
+        }
+        next
+    }
+}
+
+/// Undo and redo stacks around the current state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    past: Vec<ViewState>,
+    present: ViewState,
+    future: Vec<ViewState>,
+}
+
+impl History {
+    pub fn new(present: ViewState) -> Self {
+        Self {
+            past: Vec::new(),
+            present,
+            future: Vec::new(),
+        }
+    }
+
+    /// Apply `action`, unless it changes nothing. Returns whether it did.
+    pub fn dispatch(&mut self, action: Action) -> bool {
+        let next = self.present.reduce(action);
+        if next == self.present {
+            return false;
+        }
+        self.past.push(std::mem::replace(&mut self.present, next));
+        if self.past.len() > MAX_HISTORY {
+            self.past.remove(0);
+        }
+        self.future.clear();
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(previous) = self.past.pop() else {
+            return false;
+        };
+        self.future.push(std::mem::replace(&mut self.present, previous));
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(next) = self.future.pop() else {
+            return false;
+        };
+        self.past.push(std::mem::replace(&mut self.present, next));
+        true
+    }
+}
+
+/// The app's store. Read through the memos, which only change when their
+/// part of the state does, and change it with `dispatch`.
+#[derive(Debug, Clone, Copy)]
+pub struct AppState {
+    history: RwSignal<History>,
+    pub position_time: Memo<(Position, DateTime<Utc>)>,
+    pub selected: Memo<Option<TargetId>>,
+    pub layers: Memo<Layers>,
+}
+
+impl AppState {
+    pub fn new(position: Position, time: DateTime<Utc>) -> Self {
+        let history = create_rw_signal(History::new(ViewState::new(position, time)));
+        Self {
+            history,
+            position_time: create_memo(move |_| {
+                history.with(|h| (h.present.position.clone(), h.present.time))
+            }),
+            selected: create_memo(move |_| history.with(|h| h.present.selected.clone())),
+            layers: create_memo(move |_| history.with(|h| h.present.layers)),
+        }
+    }
+
+    pub fn dispatch(&self, action: Action) {
+        self.history.update(|h| {
+            h.dispatch(action);
+        });
+    }
+
+    pub fn undo(&self) {
+        self.history.update(|h| {
+            h.undo();
+        });
+    }
+
+    pub fn redo(&self) {
+        self.history.update(|h| {
+            h.redo();
+        });
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.with(History::can_undo)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.with(History::can_redo)
+    }
+
+    /// Keep the layers in local storage under `key`, starting from the
+    /// stored ones. Restoring them isn't a change that can be undone.
+    pub fn persist_layers(&self, key: &str) {
+        let (stored, set_stored, _) = use_local_storage::<Layers, JsonCodec>(key);
+        let stored = stored.get_untracked();
+        self.history.update(|h| h.present.layers = stored);
+
+        let layers = self.layers;
+        create_effect(move |_| set_stored.set(layers.get()));
+    }
+}
+
+pub fn use_app_state() -> AppState {
+    use_context::<AppState>()
+        .expect("AppState missing from context, provide_app_state() must run in an ancestor")
+}
+
+/// Provide the `AppState` context, starting at `position` and now, with
+/// the layers kept from the last visit.
+pub fn provide_app_state(position: Position) {
+    let state = AppState::new(position, Utc::now());
+    state.persist_layers(LAYERS_STORAGE_KEY);
+    provide_context(state);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::models::AstronObject;
+
+    fn position(lat: f64) -> Position {
+        Position {
+            lat,
+            lon: -0.0005,
+            elevation: 0.0,
+        }
+    }
+
+    fn history() -> History {
+        let time = Utc.with_ymd_and_hms(2024, 3, 20, 21, 0, 0).unwrap();
+        History::new(ViewState::new(position(51.4769), time))
+    }
+
+    #[test]
+    fn actions_change_their_part_of_the_state() {
+        let start = history().present.clone();
+        let time = Utc.with_ymd_and_hms(2024, 3, 21, 5, 0, 0).unwrap();
+        let jupiter = TargetId::from(&AstronObject::Jupiter);
+
+        let moved = start.reduce(Action::SetPosition(position(-33.9)));
+        assert_eq!(moved.position, position(-33.9));
+        assert_eq!(moved.time, start.time);
+
+        assert_eq!(start.reduce(Action::SetTime(time)).time, time);
+
+        let selected = start.reduce(Action::ToggleSelected(jupiter.clone()));
+        assert_eq!(selected.selected, Some(jupiter.clone()));
+        assert_eq!(selected.reduce(Action::ToggleSelected(jupiter)).selected, None);
+
+        let layers = start.reduce(Action::ToggleLayer(Layer::Meridian)).layers;
+        assert!(layers.shows(Layer::Meridian));
+        assert!(!layers.shows(Layer::BelowHorizon));
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_history() {
+        let mut history = history();
+        let start = history.present.clone();
+        assert!(!history.can_undo() && !history.can_redo());
+
+        assert!(history.dispatch(Action::SetPosition(position(-33.9))));
+        assert!(history.dispatch(Action::ToggleLayer(Layer::BelowHorizon)));
+        let end = history.present.clone();
+
+        assert!(history.undo());
+        assert_eq!(history.present.position, position(-33.9));
+        assert!(!history.present.layers.below_horizon);
+        assert!(history.undo());
+        assert_eq!(history.present, start);
+        assert!(!history.undo());
+
+        assert!(history.redo());
+        assert!(history.redo());
+        assert_eq!(history.present, end);
+        assert!(!history.redo());
+    }
+
+    #[test]
+    fn new_actions_drop_the_redo_stack() {
+        let mut history = history();
+        history.dispatch(Action::ToggleLayer(Layer::Meridian));
+        history.undo();
+        assert!(history.can_redo());
+        history.dispatch(Action::ToggleLayer(Layer::BelowHorizon));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn actions_that_change_nothing_are_not_recorded() {
+        let mut history = history();
+        assert!(!history.dispatch(Action::Select(None)));
+        assert!(!history.dispatch(Action::SetPosition(position(51.4769))));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = history();
+        for _ in 0..MAX_HISTORY + 10 {
+            history.dispatch(Action::ToggleLayer(Layer::Meridian));
+        }
+        let mut undone = 0;
+        while history.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_HISTORY);
+    }
+}
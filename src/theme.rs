@@ -0,0 +1,266 @@
+use std::{fmt, str::FromStr};
+
+use chrono::Utc;
+use enum_iterator::Sequence;
+use leptos::*;
+use leptos_use::{
+    storage::{use_local_storage, StringCodec},
+    use_interval_fn, use_preferred_dark,
+};
+use wasm_bindgen::JsCast;
+
+use crate::{
+    almanac::{sun_altitude, SUNSET_ALTITUDE},
+    models::Position,
+};
+
+const THEME_STORAGE_KEY: &str = "planet-tracker-theme";
+
+/// Every colour used by the app. The same values back the tailwind colour
+/// tokens through CSS variables set on the document root.
+#[derive(Debug, PartialEq)]
+pub struct Palette {
+    /// page background
+    pub surface: &'static str,
+    /// text and plot lines
+    pub ink: &'static str,
+    pub muted: &'static str,
+    /// input and table borders
+    pub line: &'static str,
+    pub line_strong: &'static str,
+    /// selected rows and active buttons
+    pub highlight: &'static str,
+    /// focus rings
+    pub accent: &'static str,
+    /// the sky below the horizon
+    pub ground: &'static str,
+    /// tooltips and the detail panel
+    pub panel: &'static str,
+    pub panel_ink: &'static str,
+    pub danger: &'static str,
+    /// markers for objects below the horizon
+    pub below_horizon: &'static str,
+}
+
+const LIGHT: Palette = Palette {
+    surface: "#ffffff",
+    ink: "#1f2937",
+    muted: "#71717a",
+    line: "#d1d5db",
+    line_strong: "#6b7280",
+    highlight: "#e4e4e7",
+    accent: "#2563eb",
+    ground: "#e5e7eb",
+    panel: "#52525b",
+    panel_ink: "#fafafa",
+    danger: "#b91c1c",
+    below_horizon: "rgb(180,180,180)",
+};
+
+const DARK: Palette = Palette {
+    surface: "#18181b",
+    ink: "#e4e4e7",
+    muted: "#a1a1aa",
+    line: "#3f3f46",
+    line_strong: "#71717a",
+    highlight: "#3f3f46",
+    accent: "#60a5fa",
+    ground: "#27272a",
+    panel: "#3f3f46",
+    panel_ink: "#fafafa",
+    danger: "#f87171",
+    below_horizon: "#52525b",
+};
+
+/// Red on black only, to keep dark adaptation at the eyepiece.
+const NIGHT: Palette = Palette {
+    surface: "#000000",
+    ink: "#b91c1c",
+    muted: "#7f1d1d",
+    line: "#450a0a",
+    line_strong: "#991b1b",
+    highlight: "#2b0505",
+    accent: "#dc2626",
+    ground: "#1c0303",
+    panel: "#1c0303",
+    panel_ink: "#dc2626",
+    danger: "#ef4444",
+    below_horizon: "#450a0a",
+};
+
+/// Black on white for paper. The print stylesheet in input.css sets the
+/// same values, since printing doesn't go through the theme context.
+const PRINT: Palette = Palette {
+    surface: "#ffffff",
+    ink: "#000000",
+    muted: "#404040",
+    line: "#000000",
+    line_strong: "#000000",
+    highlight: "#ffffff",
+    accent: "#000000",
+    ground: "#d4d4d4",
+    panel: "#ffffff",
+    panel_ink: "#000000",
+    danger: "#000000",
+    below_horizon: "#a3a3a3",
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum ThemeKind {
+    Light,
+    Dark,
+    Night,
+    Print,
+}
+
+impl ThemeKind {
+    pub fn palette(&self) -> &'static Palette {
+        match self {
+            Self::Light => &LIGHT,
+            Self::Dark => &DARK,
+            Self::Night => &NIGHT,
+            Self::Print => &PRINT,
+        }
+    }
+
+    fn css_variables(&self) -> [(&'static str, &'static str); 12] {
+        let p = self.palette();
+        [
+            ("--color-surface", p.surface),
+            ("--color-ink", p.ink),
+            ("--color-muted", p.muted),
+            ("--color-line", p.line),
+            ("--color-line-strong", p.line_strong),
+            ("--color-highlight", p.highlight),
+            ("--color-accent", p.accent),
+            ("--color-ground", p.ground),
+            ("--color-panel", p.panel),
+            ("--color-panel-ink", p.panel_ink),
+            ("--color-danger", p.danger),
+            ("--color-below-horizon", p.below_horizon),
+        ]
+    }
+}
+
+/// What the user picked. `Auto` follows the system light/dark preference
+/// during the day and switches to `Night` once the Sun has set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Sequence)]
+pub enum ThemeChoice {
+    #[default]
+    Auto,
+    Light,
+    Dark,
+    Night,
+}
+
+impl ThemeChoice {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Auto => "Auto",
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+            Self::Night => "Night vision",
+        }
+    }
+}
+
+impl fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Light => write!(f, "light"),
+            Self::Dark => write!(f, "dark"),
+            Self::Night => write!(f, "night"),
+        }
+    }
+}
+
+impl FromStr for ThemeChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "light" => Ok(Self::Light),
+            "dark" => Ok(Self::Dark),
+            "night" => Ok(Self::Night),
+            _ => Err(format!("unknown theme {}", s)),
+        }
+    }
+}
+
+/// whether the Sun is below the horizon right now at `position`
+pub fn sun_has_set(position: &Position) -> bool {
+    sun_altitude(&Utc::now().naive_utc(), position) < SUNSET_ALTITUDE
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub choice: Signal<ThemeChoice>,
+    pub set_choice: WriteSignal<ThemeChoice>,
+    pub kind: Memo<ThemeKind>,
+}
+
+impl Theme {
+    pub fn palette(&self) -> &'static Palette {
+        self.kind.get().palette()
+    }
+}
+
+pub fn use_theme() -> Theme {
+    use_context::<Theme>()
+        .expect("Theme missing from context, provide_theme() must run in an ancestor")
+}
+
+/// Provide the `Theme` context. The user's choice is kept in local storage;
+/// `position` is where the observer is, for working out when the Sun sets.
+pub fn provide_theme(position: Signal<Option<Position>>) {
+    let (choice, set_choice, _) = use_local_storage::<ThemeChoice, StringCodec>(THEME_STORAGE_KEY);
+    let prefers_dark = use_preferred_dark();
+
+    let (night, set_night) = create_signal(false);
+    let check_night = move || {
+        let has_set = position.get_untracked().is_some_and(|p| sun_has_set(&p));
+        if has_set != night.get_untracked() {
+            set_night.set(has_set);
+        }
+    };
+    create_effect(move |_| {
+        position.track();
+        check_night();
+    });
+    let _ = use_interval_fn(check_night, 60_000);
+
+    let kind = create_memo(move |_| match choice.get() {
+        ThemeChoice::Light => ThemeKind::Light,
+        ThemeChoice::Dark => ThemeKind::Dark,
+        ThemeChoice::Night => ThemeKind::Night,
+        ThemeChoice::Auto if night.get() => ThemeKind::Night,
+        ThemeChoice::Auto if prefers_dark.get() => ThemeKind::Dark,
+        ThemeChoice::Auto => ThemeKind::Light,
+    });
+
+    create_effect(move |_| {
+        let kind = kind.get();
+        let Some(root) = document().document_element() else {
+            return;
+        };
+        let Ok(root) = root.dyn_into::<web_sys::HtmlElement>() else {
+            return;
+        };
+        let style = root.style();
+        for (name, value) in kind.css_variables() {
+            let _ = style.set_property(name, value);
+        }
+        let _ = style.set_property(
+            "color-scheme",
+            if kind == ThemeKind::Light { "light" } else { "dark" },
+        );
+    });
+
+    provide_context(Theme {
+        choice,
+        set_choice,
+        kind,
+    });
+}
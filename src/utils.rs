@@ -1,6 +1,8 @@
 use std::f64::consts::PI;
 
-use crate::models::CardinalDirection;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+
+use crate::models::{CardinalDirection, SkyTarget};
 
 pub fn rad2deg(rad: f64) -> f64 
 {
@@ -8,13 +10,65 @@ pub fn rad2deg(rad: f64) -> f64
 }
 
 
+/// the nearest of the eight compass points to a bearing in degrees, which
+/// can be outside 0° to 360°
 pub fn deg2cardinal(deg: f64) -> CardinalDirection
 {
-    let mut shifted = deg + 22.5;
-    if shifted > 360.0 {
-        shifted = shifted - 360.0; 
-    }
-    let i = (shifted / 45.0).ceil() as u8;
-    CardinalDirection::from(i)
+    // each point covers the 45° centred on it, so north runs from 337.5° to 22.5°
+    let sector = ((deg + 22.5).rem_euclid(360.0) / 45.0).floor() as u8;
+    // rem_euclid can round up to 360.0 for bearings just under a multiple of 360°
+    CardinalDirection::from(sector % 8 + 1)
+}
+
+/// the server sends naive datetimes in UTC; convert them for display
+pub fn naive_utc_to_local(dt: &NaiveDateTime) -> DateTime<Local>
+{
+    Utc.from_local_datetime(dt).unwrap().into()
 }
 
+/// `obj`'s rise or set `time` as local time, or what it means that there
+/// isn't one
+pub fn format_crossing(obj: &SkyTarget, time: Option<NaiveDateTime>, format: &str) -> String
+{
+    time.map_or_else(
+        || obj.missing_crossing().to_string(),
+        |time| naive_utc_to_local(&time).format(format).to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cardinal(deg: f64) -> String {
+        format!("{:#}", deg2cardinal(deg))
+    }
+
+    #[test]
+    fn cardinal_points() {
+        let expected = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+        for (i, name) in expected.iter().enumerate() {
+            let deg = 45.0 * i as f64;
+            assert_eq!(cardinal(deg), *name, "{}°", deg);
+            assert_eq!(cardinal(deg - 22.0), *name, "{}°", deg - 22.0);
+            assert_eq!(cardinal(deg + 22.0), *name, "{}°", deg + 22.0);
+        }
+    }
+
+    #[test]
+    fn cardinal_out_of_range() {
+        assert_eq!(cardinal(360.0), "N");
+        assert_eq!(cardinal(359.999), "N");
+        assert_eq!(cardinal(337.5), "N");
+        assert_eq!(cardinal(-22.5), "N");
+        assert_eq!(cardinal(-1e-15), "N");
+        assert_eq!(cardinal(-90.0), "W");
+        assert_eq!(cardinal(720.0 + 90.0), "E");
+    }
+
+    #[test]
+    fn radians_to_degrees() {
+        assert_eq!(rad2deg(PI), 180.0);
+        assert_eq!(rad2deg(-PI / 2.0), -90.0);
+    }
+}
@@ -4,7 +4,25 @@ module.exports = {
       files: ["*.html", "./src/**/*.rs"],
     },
     theme: {
-      extend: {},
+      extend: {
+        // set from the active palette in src/theme.rs
+        colors: {
+          surface: "var(--color-surface)",
+          ink: "var(--color-ink)",
+          muted: "var(--color-muted)",
+          line: "var(--color-line)",
+          "line-strong": "var(--color-line-strong)",
+          highlight: "var(--color-highlight)",
+          accent: "var(--color-accent)",
+          ground: "var(--color-ground)",
+          panel: "var(--color-panel)",
+          "panel-ink": "var(--color-panel-ink)",
+          danger: "var(--color-danger)",
+        },
+        borderColor: {
+          DEFAULT: "var(--color-line)",
+        },
+      },
     },
     plugins: [],
   }
\ No newline at end of file